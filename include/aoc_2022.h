@@ -0,0 +1,40 @@
+/* Generated by `capi::header()` in advent-of-code-2022, don't edit by hand. */
+
+#ifndef AOC_2022_H
+#define AOC_2022_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef int32_t aoc_status;
+
+/* the answer was written to `out_buf` */
+#define AOC_OK 0
+/* there's no solver for the year, day and part */
+#define AOC_UNKNOWN_PUZZLE 1
+/* the input isn't valid UTF-8 */
+#define AOC_INVALID_INPUT 2
+/* the solver panicked, most likely because it couldn't parse the input */
+#define AOC_SOLVER_FAILED 3
+/* `out_buf` is too small, `*out_len` is set to the required length */
+#define AOC_BUFFER_TOO_SMALL 4
+/* one of the pointers was null */
+#define AOC_NULL_POINTER 5
+
+/*
+ * Solves the puzzle for the year, day and part with the `len` bytes of input at `input_ptr`.
+ * `*out_len` must be the capacity of `out_buf`. The answer is written to `out_buf` without
+ * a NUL terminator, and `*out_len` is set to its length.
+ */
+aoc_status aoc_solve(uint32_t year, uint32_t day, uint32_t part, const uint8_t *input_ptr,
+                     size_t len, uint8_t *out_buf, size_t *out_len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* AOC_2022_H */
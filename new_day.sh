@@ -11,5 +11,6 @@ mkdir -p "$SRC/$mod_name"
 sed "s|xx|$day|g" "$SRC/day_xx/mod.rs" > "$SRC/$mod_name/mod.rs"
 printf "pub mod $mod_name;\n" >> "$SRC/lib.rs"
 sed -i -zE 's/(.*)(\n\s*println!\("done.*)/\1    run_day_with_generator!('"$mod_name, \"$day\");\n\2/" "$SRC/bin/bin.rs"
+sed -i -zE "s/(.*)(\n\];.*)/\1\n    day_with_generator!($mod_name, $day),\2/" "$SRC/runner/mod.rs"
 
 curl "https://adventofcode.com/2022/day/$day/input" --cookie "$(cat cookies)" > "input/2022/day$day.txt"
\ No newline at end of file
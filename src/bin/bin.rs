@@ -1,6 +1,16 @@
-use std::time::Instant;
+use std::cell::OnceCell;
+use std::fs;
+use std::panic;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
+use advent_of_code_2022::runner::cache::{self, Cache};
+use advent_of_code_2022::runner::{
+    get_day, run, timings_unreliable, with_timeout, Day, Failure, Outcome, DAYS,
+};
 use advent_of_code_2022::*;
+use itertools::Itertools;
 
 #[cfg(feature = "io")]
 macro_rules! input_str {
@@ -18,17 +28,31 @@ macro_rules! input_str {
 
 macro_rules! run_parts {
     ($m:ident, $d:expr, $g:expr) => {
+        let unreliable = timings_unreliable();
         let instant = Instant::now();
         let input = input_str!($d);
-        let processed_input = $g(&input);
-        println!(
-            "day {0}-1: {1}\nday {0}-2: {2}",
-            $d,
-            $m::part_1(&processed_input),
-            $m::part_2(&processed_input)
-        );
+        let answers = with_timeout(TIMEOUT.get().copied(), move || {
+            // only parse the input if one of the answers isn't cached
+            let processed_input = OnceCell::new();
+            let processed_input = || processed_input.get_or_init(|| $g(&input));
+            [
+                solve_cached($d, 1, &input, || $m::part_1(processed_input()).to_string()),
+                solve_cached($d, 2, &input, || $m::part_2(processed_input()).to_string()),
+            ]
+        });
+        match answers {
+            Ok([part_1, part_2]) => println!("day {0}-1: {1}\nday {0}-2: {2}", $d, part_1, part_2),
+            Err(failure) => println!("day {}: {failure}", $d),
+        }
 
-        println!("{:?}\n", instant.elapsed());
+        if unreliable {
+            println!(
+                "{:?} (unreliable, a timed-out day is still running)\n",
+                instant.elapsed()
+            );
+        } else {
+            println!("{:?}\n", instant.elapsed());
+        }
     };
 }
 
@@ -44,7 +68,160 @@ macro_rules! run_day {
     };
 }
 
-pub fn main() {
+static CACHE: OnceLock<Cache> = OnceLock::new();
+static TIMEOUT: OnceLock<Duration> = OnceLock::new();
+
+fn solve_cached<F: FnOnce() -> String>(day: &str, part: u32, input: &str, solve: F) -> String {
+    match CACHE.get() {
+        Some(cache) => {
+            let day = day
+                .parse()
+                .ok()
+                .and_then(get_day)
+                .expect("day not registered");
+            cache.get_or_solve(day, part, input, solve)
+        }
+        None => solve(),
+    }
+}
+
+// An input is flagged as slow when a part takes this many times longer than
+// the median over all the inputs for that day.
+const SLOW_FACTOR: u32 = 5;
+
+fn input_paths(day: u32) -> Vec<PathBuf> {
+    match fs::read_dir(format!("input/2022/day{day}")) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "txt"))
+            .sorted()
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+fn parse_days(args: &[&str]) -> Vec<&'static Day> {
+    if args.is_empty() {
+        return DAYS.iter().collect();
+    }
+    args.iter()
+        .map(|a| {
+            a.parse()
+                .ok()
+                .and_then(get_day)
+                .unwrap_or_else(|| panic!("unknown day '{a}'"))
+        })
+        .collect()
+}
+
+fn median(mut times: Vec<Duration>) -> Option<Duration> {
+    times.sort();
+    times.get(times.len() / 2).copied()
+}
+
+fn format_outcome(outcome: &Outcome) -> String {
+    match &outcome.answer {
+        Ok(answer) => format!("{answer} ({:?})", outcome.elapsed),
+        Err(Failure::Panic(_)) => format!("PANIC ({:?})", outcome.elapsed),
+        Err(Failure::Timeout) => format!("TIMEOUT ({:?})", outcome.elapsed),
+    }
+}
+
+fn compare_day(day: &Day, paths: &[PathBuf]) {
+    let results = paths
+        .iter()
+        .map(|path| {
+            let input = fs::read_to_string(path).expect("couldn't read input file");
+            let timeout = TIMEOUT.get().copied();
+            [
+                run(day.part_1, &input, timeout),
+                run(day.part_2, &input, timeout),
+            ]
+        })
+        .collect_vec();
+
+    let medians = [0, 1].map(|part| {
+        median(
+            results
+                .iter()
+                .filter(|r| r[part].answer.is_ok() && !r[part].unreliable)
+                .map(|r| r[part].elapsed)
+                .collect(),
+        )
+    });
+
+    let names = paths
+        .iter()
+        .map(|p| p.file_name().unwrap_or(p.as_os_str()).to_string_lossy())
+        .collect_vec();
+    let cells = results
+        .iter()
+        .map(|r| r.each_ref().map(format_outcome))
+        .collect_vec();
+    let name_width = names.iter().map(|n| n.len()).max().unwrap_or(0);
+    let part_1_width = cells.iter().map(|c| c[0].len()).max().unwrap_or(0);
+
+    println!("day {}", day.day);
+    for ((name, cells), result) in names.iter().zip(&cells).zip(&results) {
+        let mut notes = vec![];
+        for (part, outcome) in result.iter().enumerate() {
+            match (&outcome.answer, medians[part]) {
+                (Err(failure), _) => notes.push(format!("part {} {failure}", part + 1)),
+                (Ok(_), _) if outcome.unreliable => notes.push(format!(
+                    "part {} timing unreliable, a timed-out solver is still running",
+                    part + 1
+                )),
+                (Ok(_), Some(median))
+                    if paths.len() > 1 && outcome.elapsed > median * SLOW_FACTOR =>
+                {
+                    notes.push(format!(
+                        "part {} slow: {:.1}x median",
+                        part + 1,
+                        outcome.elapsed.as_secs_f64() / median.as_secs_f64()
+                    ))
+                }
+                _ => {}
+            }
+        }
+        println!(
+            "  {name:name_width$} | {:part_1_width$} | {}{}",
+            cells[0],
+            cells[1],
+            if notes.is_empty() {
+                String::new()
+            } else {
+                format!("  <- {}", notes.join("; "))
+            }
+        );
+    }
+    println!();
+}
+
+// Runs every solver against every input in `input/2022/day{n}/*.txt`, which is useful
+// for checking that a solution doesn't only work on one person's input.
+// The cache is never used here since the timings are the point.
+fn compare(args: &[&str]) {
+    // panics are reported alongside the answers instead
+    panic::set_hook(Box::new(|_| {}));
+
+    let days = parse_days(args);
+    for day in days {
+        let paths = input_paths(day.day);
+        if !paths.is_empty() {
+            compare_day(day, &paths);
+        }
+    }
+}
+
+// Prints the day 1 calorie report for the given input file (or the usual input).
+fn report(path: Option<&str>) {
+    let input = fs::read_to_string(path.unwrap_or("input/2022/day1.txt"))
+        .expect("couldn't read input file");
+    print!("{}", day_01::report(&day_01::input_generator(&input)));
+}
+
+fn run_all() {
     let instant = Instant::now();
     run_day_with_generator!(day_01, "1");
     run_day_with_generator!(day_02, "2");
@@ -74,3 +251,39 @@ pub fn main() {
 
     println!("done in {:?}", instant.elapsed());
 }
+
+fn usage_error(msg: &str) -> ! {
+    eprintln!("{msg}");
+    std::process::exit(2)
+}
+
+pub fn main() {
+    let mut args = std::env::args().skip(1).collect_vec();
+    let use_cache = !args.iter().any(|a| a == "--no-cache")
+        && (args.iter().any(|a| a == "--cache") || std::env::var_os("AOC_CACHE").is_some());
+    args.retain(|a| a != "--cache" && a != "--no-cache");
+    if let Some(idx) = args.iter().position(|a| a == "--timeout") {
+        let timeout = args
+            .get(idx + 1)
+            .and_then(|s| s.parse().ok())
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+            .unwrap_or_else(|| usage_error("--timeout expects a non-negative number of seconds"));
+        let _ = TIMEOUT.set(timeout);
+        args.drain(idx..=idx + 1);
+    }
+
+    match args.iter().map(String::as_str).collect_vec().as_slice() {
+        ["compare", days @ ..] => compare(days),
+        ["report", "1", path @ ..] if path.len() <= 1 => report(path.first().copied()),
+        ["cache", "clear"] => Cache::new(cache::DEFAULT_DIR)
+            .clear()
+            .expect("couldn't clear the cache"),
+        [] => {
+            if use_cache {
+                let _ = CACHE.set(Cache::new(cache::DEFAULT_DIR));
+            }
+            run_all()
+        }
+        args => panic!("unexpected arguments: {args:?}"),
+    }
+}
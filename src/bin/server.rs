@@ -0,0 +1,219 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use advent_of_code_2022::json;
+use advent_of_code_2022::runner::{get_day, run, Failure};
+use itertools::Itertools;
+
+const DEFAULT_ADDR: &str = "127.0.0.1:8022";
+const TIMEOUT: Duration = Duration::from_secs(60);
+// how long to wait on a slow client before giving up on the request
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+// the largest request body (the puzzle input) and request or header line that are accepted
+const MAX_BODY: usize = 16 << 20;
+const MAX_LINE: u64 = 8 << 10;
+
+struct Response {
+    status: u16,
+    body: String,
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        504 => "Gateway Timeout",
+        _ => "Internal Server Error",
+    }
+}
+
+fn error(status: u16, msg: &str) -> Response {
+    Response {
+        status,
+        body: format!("{{\"error\":{}}}", json::string(msg)),
+    }
+}
+
+fn parse_route(path: &str) -> Option<(u32, u32)> {
+    match path.trim_matches('/').split('/').collect_vec().as_slice() {
+        ["2022", "day", day, "part", part] => Some((day.parse().ok()?, part.parse().ok()?)),
+        _ => None,
+    }
+}
+
+fn handle(method: &str, path: &str, body: Vec<u8>) -> Response {
+    let Some((day, part)) = parse_route(path) else {
+        return error(404, "expected a path like /2022/day/{n}/part/{p}");
+    };
+    let Some(solver) = get_day(day).and_then(|d| d.part(part)) else {
+        return error(404, &format!("no solver for day {day} part {part}"));
+    };
+    if method != "POST" {
+        return error(405, "the puzzle input must be POSTed");
+    }
+    let Ok(input) = String::from_utf8(body) else {
+        return error(400, "the puzzle input must be UTF-8");
+    };
+
+    let outcome = run(solver, &input, Some(TIMEOUT));
+    match outcome.answer {
+        Ok(answer) => Response {
+            status: 200,
+            body: format!(
+                "{{\"day\":{day},\"part\":{part},\"answer\":{},\"elapsed_us\":{}}}",
+                json::string(&answer),
+                outcome.elapsed.as_micros()
+            ),
+        },
+        // The solutions panic on input they can't parse, so that's the likeliest cause. They also
+        // panic on their own bugs though, and there's no telling the two apart from here, so
+        // those are reported as a 422 as well.
+        Err(Failure::Panic(msg)) => error(422, &msg),
+        Err(Failure::Timeout) => error(504, "the solver timed out"),
+    }
+}
+
+fn read_line<R: BufRead>(reader: &mut R, line: &mut String) -> Result<(), Response> {
+    line.clear();
+    match reader.take(MAX_LINE).read_line(line) {
+        Ok(_) if line.ends_with('\n') => Ok(()),
+        Ok(_) if line.len() as u64 == MAX_LINE => Err(error(400, "request line too long")),
+        _ => Err(error(400, "malformed request")),
+    }
+}
+
+fn read_request<R: Read>(stream: R) -> Result<(String, String, Vec<u8>), Response> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    read_line(&mut reader, &mut line)?;
+    let (method, path) = line
+        .split_whitespace()
+        .next_tuple()
+        .ok_or_else(|| error(400, "malformed request"))?;
+    let (method, path) = (method.to_string(), path.to_string());
+
+    let mut content_length = 0;
+    loop {
+        read_line(&mut reader, &mut line)?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| error(400, "invalid Content-Length"))?;
+            }
+        }
+    }
+
+    if content_length > MAX_BODY {
+        return Err(error(
+            413,
+            &format!("the puzzle input must be at most {MAX_BODY} bytes"),
+        ));
+    }
+    let mut body = vec![];
+    reader
+        .take(content_length as u64)
+        .read_to_end(&mut body)
+        .map_err(|_| error(400, "couldn't read the request body"))?;
+    if body.len() < content_length {
+        return Err(error(
+            400,
+            "the request body is shorter than its Content-Length",
+        ));
+    }
+    Ok((method, path, body))
+}
+
+fn serve(mut stream: TcpStream) {
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let response = match read_request(&mut stream) {
+        Ok((method, path, body)) => handle(&method, &path, body),
+        Err(response) => response,
+    };
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason(response.status),
+        response.body.len(),
+        response.body
+    );
+}
+
+// Serves `POST /2022/day/{n}/part/{p}` with the puzzle input as the body.
+pub fn main() {
+    let addr = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_ADDR.to_string());
+    let listener = TcpListener::bind(&addr).expect("couldn't bind to address");
+    println!("listening on {addr}");
+    for stream in listener.incoming().filter_map(Result::ok) {
+        thread::spawn(move || serve(stream));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test() {
+        let input = b"1000\n2000\n\n4000".to_vec();
+        let response = handle("POST", "/2022/day/1/part/1", input.clone());
+        assert_eq!(response.status, 200);
+        assert!(response
+            .body
+            .starts_with(r#"{"day":1,"part":1,"answer":"4000","#));
+
+        assert_eq!(handle("GET", "/2022/day/1/part/1", vec![]).status, 405);
+        assert_eq!(
+            handle("POST", "/2022/day/26/part/1", input.clone()).status,
+            404
+        );
+        assert_eq!(
+            handle("POST", "/2022/day/1/part/3", input.clone()).status,
+            404
+        );
+        assert_eq!(handle("POST", "/2022/day/1", input).status, 404);
+        assert_eq!(handle("POST", "/2022/day/1/part/1", vec![0xff]).status, 400);
+
+        let response = handle("POST", "/2022/day/1/part/1", b"one".to_vec());
+        assert_eq!(response.status, 422);
+        assert!(response.body.contains("Could not parse calories as int"));
+    }
+
+    #[test]
+    fn test_read_request() {
+        let request = b"POST /2022/day/1/part/1 HTTP/1.1\r\nContent-Length: 4\r\n\r\n1000";
+        let (method, path, body) = read_request(&request[..]).ok().unwrap();
+        assert_eq!(
+            (method.as_str(), path.as_str()),
+            ("POST", "/2022/day/1/part/1")
+        );
+        assert_eq!(body, b"1000");
+
+        let status = |request: &[u8]| read_request(request).err().unwrap().status;
+        assert_eq!(
+            status(b"POST / HTTP/1.1\r\nContent-Length: 100000000000000\r\n\r\n"),
+            413
+        );
+        assert_eq!(
+            status(b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\n1000"),
+            400
+        );
+        assert_eq!(status(b"POST / HTTP/1.1\r\nContent-Length: x\r\n\r\n"), 400);
+        assert_eq!(status(b"GET"), 400);
+        assert_eq!(status(&[b'a'; MAX_LINE as usize + 1]), 400);
+    }
+}
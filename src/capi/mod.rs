@@ -0,0 +1,125 @@
+use std::slice;
+
+use crate::runner::{get_day, run};
+
+// The C interface to the solvers. The header in `include/aoc_2022.h` is generated from
+// `header()`, which the tests check is up to date.
+
+pub const YEAR: u32 = 2022;
+
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Ok = 0,
+    UnknownPuzzle = 1,
+    InvalidInput = 2,
+    SolverFailed = 3,
+    BufferTooSmall = 4,
+    NullPointer = 5,
+}
+
+const STATUSES: [(Status, &str, &str); 6] = [
+    (Status::Ok, "AOC_OK", "the answer was written to `out_buf`"),
+    (
+        Status::UnknownPuzzle,
+        "AOC_UNKNOWN_PUZZLE",
+        "there's no solver for the year, day and part",
+    ),
+    (
+        Status::InvalidInput,
+        "AOC_INVALID_INPUT",
+        "the input isn't valid UTF-8",
+    ),
+    (
+        Status::SolverFailed,
+        "AOC_SOLVER_FAILED",
+        "the solver panicked, most likely because it couldn't parse the input",
+    ),
+    (
+        Status::BufferTooSmall,
+        "AOC_BUFFER_TOO_SMALL",
+        "`out_buf` is too small, `*out_len` is set to the required length",
+    ),
+    (
+        Status::NullPointer,
+        "AOC_NULL_POINTER",
+        "one of the pointers was null",
+    ),
+];
+
+/// # Safety
+/// `input_ptr` must point to `len` readable bytes, `out_len` must point to the capacity of
+/// `out_buf`, and `out_buf` must point to that many writable bytes.
+/// The answer is written to `out_buf` without a NUL terminator, and `*out_len` is set to its length.
+#[no_mangle]
+pub unsafe extern "C" fn aoc_solve(
+    year: u32,
+    day: u32,
+    part: u32,
+    input_ptr: *const u8,
+    len: usize,
+    out_buf: *mut u8,
+    out_len: *mut usize,
+) -> Status {
+    if input_ptr.is_null() || out_buf.is_null() || out_len.is_null() {
+        return Status::NullPointer;
+    }
+    let solver = match get_day(day).and_then(|d| d.part(part)) {
+        Some(solver) if year == YEAR => solver,
+        _ => return Status::UnknownPuzzle,
+    };
+    let Ok(input) = std::str::from_utf8(slice::from_raw_parts(input_ptr, len)) else {
+        return Status::InvalidInput;
+    };
+
+    // `run` catches any panics, which would otherwise abort when unwinding out of this function
+    let Ok(answer) = run(solver, input, None).answer else {
+        return Status::SolverFailed;
+    };
+    let capacity = *out_len;
+    *out_len = answer.len();
+    if answer.len() > capacity {
+        return Status::BufferTooSmall;
+    }
+    out_buf.copy_from_nonoverlapping(answer.as_ptr(), answer.len());
+    Status::Ok
+}
+
+pub fn header() -> String {
+    let statuses: String = STATUSES
+        .iter()
+        .map(|(status, name, doc)| format!("/* {doc} */\n#define {name} {}\n", *status as i32))
+        .collect();
+    format!(
+        "\
+/* Generated by `capi::header()` in advent-of-code-2022, don't edit by hand. */
+
+#ifndef AOC_2022_H
+#define AOC_2022_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern \"C\" {{
+#endif
+
+typedef int32_t aoc_status;
+
+{statuses}
+/*
+ * Solves the puzzle for the year, day and part with the `len` bytes of input at `input_ptr`.
+ * `*out_len` must be the capacity of `out_buf`. The answer is written to `out_buf` without
+ * a NUL terminator, and `*out_len` is set to its length.
+ */
+aoc_status aoc_solve(uint32_t year, uint32_t day, uint32_t part, const uint8_t *input_ptr,
+                     size_t len, uint8_t *out_buf, size_t *out_len);
+
+#ifdef __cplusplus
+}}
+#endif
+
+#endif /* AOC_2022_H */
+"
+    )
+}
@@ -1,30 +1,240 @@
-use arrayvec::ArrayVec;
-use lazysort::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::io::{self, BufRead, Read};
+use std::mem;
 
-pub type Input = Vec<ArrayVec<i32, 16>>;
+use rayon::prelude::*;
 
+pub type Input = Vec<Vec<i32>>;
+
+// Elves are separated by blank lines, and `lines` takes care of any `\r\n` line endings.
 pub fn input_generator(input: &str) -> Input {
-    input
-        .split("\n\n")
-        .map(|elf_food| {
-            elf_food
-                .lines()
-                .map(|line| line.parse().expect("Could not parse calories as int"))
-                .collect()
-        })
-        .collect()
+    let mut elves = vec![vec![]];
+    for line in input.lines() {
+        if line.is_empty() {
+            elves.push(vec![]);
+        } else {
+            let calories = line.parse().expect("Could not parse calories as int");
+            elves.last_mut().unwrap().push(calories);
+        }
+    }
+    elves
 }
 
 fn sum_inner(input: &Input) -> impl Iterator<Item = i32> + '_ {
     input.iter().map(|elf| elf.iter().sum())
 }
 
+// A min-heap of the `k` largest items seen so far.
+struct TopK<T> {
+    k: usize,
+    heap: BinaryHeap<Reverse<T>>,
+}
+
+impl<T: Ord> TopK<T> {
+    fn new(k: usize) -> Self {
+        Self {
+            k,
+            heap: BinaryHeap::with_capacity(k + 1),
+        }
+    }
+
+    fn push(&mut self, item: T) {
+        if self.heap.len() < self.k {
+            self.heap.push(Reverse(item));
+        } else if self.heap.peek().is_some_and(|Reverse(min)| *min < item) {
+            self.heap.pop();
+            self.heap.push(Reverse(item));
+        }
+    }
+
+    fn into_sorted_vec(self) -> Vec<T> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(item)| item)
+            .collect()
+    }
+}
+
 pub fn part_1(input: &Input) -> i32 {
     sum_inner(input).max().expect("No elf data was found.")
 }
 
 pub fn part_2(input: &Input) -> i32 {
-    sum_inner(input).sorted_by(|a, b| b.cmp(a)).take(3).sum()
+    let mut top = TopK::new(3);
+    sum_inner(input).for_each(|total| top.push(total));
+    top.into_sorted_vec().into_iter().sum()
+}
+
+pub struct ElfSummary {
+    pub index: usize,
+    pub items: usize,
+    pub total: i32,
+}
+
+pub struct Report {
+    pub elves: Vec<ElfSummary>,
+    sorted_totals: Vec<i32>,
+}
+
+const HISTOGRAM_BINS: usize = 10;
+const HISTOGRAM_WIDTH: usize = 40;
+
+impl Report {
+    pub fn mean(&self) -> f64 {
+        self.sorted_totals.iter().map(|&t| t as f64).sum::<f64>() / self.sorted_totals.len() as f64
+    }
+
+    pub fn median(&self) -> f64 {
+        let totals = &self.sorted_totals;
+        let mid = totals.len() / 2;
+        if totals.len().is_multiple_of(2) {
+            (totals[mid - 1] as f64 + totals[mid] as f64) / 2.0
+        } else {
+            totals[mid] as f64
+        }
+    }
+
+    // Uses the nearest-rank method, so the result is always one of the totals.
+    pub fn percentile(&self, p: f64) -> i32 {
+        let rank = (p / 100.0 * self.sorted_totals.len() as f64).ceil() as usize;
+        self.sorted_totals[rank.clamp(1, self.sorted_totals.len()) - 1]
+    }
+
+    // A bar for each of `bins` equal-width ranges of totals, scaled to at most `width` characters.
+    pub fn histogram(&self, bins: usize, width: usize) -> String {
+        assert!(bins > 0, "the histogram needs at least one bin");
+        let min = self.sorted_totals[0] as i64;
+        let max = *self.sorted_totals.last().unwrap() as i64;
+        let bin_width = ((max - min) / bins as i64 + 1).max(1);
+
+        let mut counts = vec![0; bins];
+        for &t in &self.sorted_totals {
+            counts[((t as i64 - min) / bin_width) as usize] += 1;
+        }
+        let most = counts.iter().copied().max().unwrap_or(0).max(1);
+        let label_width = max.to_string().len().max(min.to_string().len());
+
+        let mut out = String::new();
+        for (i, count) in counts.into_iter().enumerate() {
+            let lo = min + i as i64 * bin_width;
+            let hi = lo + bin_width - 1;
+            let bar = "#".repeat(count * width / most);
+            out.push_str(&format!(
+                "{lo:>label_width$}-{hi:<label_width$} | {bar} {count}\n"
+            ));
+        }
+        out
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "elf\titems\ttotal")?;
+        for elf in &self.elves {
+            writeln!(f, "{}\t{}\t{}", elf.index, elf.items, elf.total)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "elves:  {}", self.elves.len())?;
+        writeln!(f, "mean:   {:.1}", self.mean())?;
+        writeln!(f, "median: {:.1}", self.median())?;
+        for p in [10.0, 25.0, 75.0, 90.0, 99.0] {
+            writeln!(f, "p{p}:    {}", self.percentile(p))?;
+        }
+        writeln!(f)?;
+        write!(f, "{}", self.histogram(HISTOGRAM_BINS, HISTOGRAM_WIDTH))
+    }
+}
+
+pub fn report(input: &Input) -> Report {
+    assert!(!input.is_empty(), "No elf data was found.");
+    let elves = input
+        .iter()
+        .zip(sum_inner(input))
+        .enumerate()
+        .map(|(index, (elf, total))| ElfSummary {
+            index,
+            items: elf.len(),
+            total,
+        })
+        .collect::<Vec<_>>();
+    let mut sorted_totals = elves.iter().map(|e| e.total).collect::<Vec<_>>();
+    sorted_totals.sort_unstable();
+    Report {
+        elves,
+        sorted_totals,
+    }
+}
+
+// The number of bytes read for each of the chunks that are summed in parallel.
+const CHUNK_LEN: usize = 1 << 22;
+
+fn chunk_top_k(chunk: &[u8], k: usize) -> io::Result<TopK<i64>> {
+    let mut top = TopK::new(k);
+    let mut total = None;
+    for line in chunk.split(|&b| b == b'\n') {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.is_empty() {
+            total.take().into_iter().for_each(|t| top.push(t));
+            continue;
+        }
+        let calories: i64 = std::str::from_utf8(line)
+            .ok()
+            .and_then(|l| l.parse().ok())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Could not parse calories as int",
+                )
+            })?;
+        *total.get_or_insert(0) += calories;
+    }
+    total.into_iter().for_each(|t| top.push(t));
+    Ok(top)
+}
+
+// The start of the last blank line (which may be `\r\n`), so that splitting there leaves only
+// whole elves before it.
+fn last_elf_boundary(buf: &[u8]) -> Option<usize> {
+    (1..buf.len())
+        .rev()
+        .find(|&i| buf[i - 1] == b'\n' && (buf[i] == b'\n' || buf[i..].starts_with(b"\r\n")))
+}
+
+// Finds the `k` largest calorie totals (in descending order) without reading the whole input into
+// memory. The input is read in chunks that are split on blank lines and summed in parallel.
+pub fn top_k_totals<R: BufRead>(mut reader: R, k: usize) -> io::Result<Vec<i64>> {
+    let mut top = TopK::new(k);
+    let mut carry = vec![];
+    let mut eof = false;
+    while !eof {
+        let mut chunks = vec![];
+        while chunks.len() < rayon::current_num_threads() && !eof {
+            let mut buf = mem::take(&mut carry);
+            eof = (&mut reader).take(CHUNK_LEN as u64).read_to_end(&mut buf)? == 0;
+            match last_elf_boundary(&buf) {
+                _ if eof => chunks.push(buf),
+                Some(split) => {
+                    carry = buf.split_off(split);
+                    chunks.push(buf);
+                }
+                // this elf is carrying more than a chunk's worth, so keep reading
+                None => carry = buf,
+            }
+        }
+
+        let partials = chunks
+            .par_iter()
+            .map(|chunk| chunk_top_k(chunk, k))
+            .collect::<io::Result<Vec<_>>>()?;
+        partials
+            .into_iter()
+            .flat_map(TopK::into_sorted_vec)
+            .for_each(|total| top.push(total));
+    }
+    Ok(top.into_sorted_vec())
 }
 
 #[cfg(test)]
@@ -38,4 +248,79 @@ mod tests {
         assert_eq!(part_1(&input), 24000);
         assert_eq!(part_2(&input), 45000);
     }
+
+    #[test]
+    fn test_report() {
+        let input =
+            input_generator("1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000");
+        let report = report(&input);
+        assert_eq!(report.elves[3].index, 3);
+        assert_eq!(report.elves[3].items, 3);
+        assert_eq!(report.elves[3].total, 24000);
+        assert_eq!(report.mean(), 11000.0);
+        assert_eq!(report.median(), 10000.0);
+        assert_eq!(report.percentile(0.0), 4000);
+        assert_eq!(report.percentile(50.0), 10000);
+        assert_eq!(report.percentile(80.0), 11000);
+        assert_eq!(report.percentile(100.0), 24000);
+        assert_eq!(
+            report.histogram(2, 4),
+            " 4000-14000 | #### 4\n14001-24001 | # 1\n"
+        );
+
+        // generated inventories can have any number of items per elf
+        let input = input_generator(&format!("{}\n2\n\n3", "1\n".repeat(1000)));
+        let report = super::report(&input);
+        assert_eq!(report.elves[0].items, 1000);
+        assert_eq!(report.elves[2].total, 3);
+        assert_eq!(report.percentile(100.0), 1000);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one bin")]
+    fn test_histogram_bins() {
+        let input = input_generator("1000\n\n2000");
+        report(&input).histogram(0, 10);
+    }
+
+    #[test]
+    fn test_input() {
+        let input = input_generator("1000\r\n2000\r\n\r\n4000\r\n");
+        assert_eq!(input, [vec![1000, 2000], vec![4000]]);
+
+        // more items than fit in a fixed-size elf
+        let input = input_generator(&format!("{}\n7", "1\n".repeat(100)));
+        assert_eq!(input[0].len(), 100);
+        assert_eq!(part_1(&input), 100);
+        assert_eq!(part_2(&input), 107);
+    }
+
+    #[test]
+    fn test_top_k_totals() {
+        let input = "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000\n";
+        assert_eq!(
+            top_k_totals(input.as_bytes(), 3).unwrap(),
+            [24000, 11000, 10000]
+        );
+        assert_eq!(top_k_totals(input.as_bytes(), 0).unwrap(), []);
+        assert_eq!(top_k_totals(input.as_bytes(), 10).unwrap().len(), 5);
+        assert!(top_k_totals("1\nx\n".as_bytes(), 1).is_err());
+        assert_eq!(
+            top_k_totals(input.replace('\n', "\r\n").as_bytes(), 2).unwrap(),
+            [24000, 11000]
+        );
+        assert_eq!(last_elf_boundary(b"1\r\n\r\n2\r\n\r\n3\r\n"), Some(8));
+        assert_eq!(last_elf_boundary(b"1\n\n2\n"), Some(2));
+        assert_eq!(last_elf_boundary(b"1\r\n2\r\n"), None);
+
+        // span several chunks, including an elf that doesn't fit in one
+        let mut big = "1\n".repeat(CHUNK_LEN);
+        for i in 0..(3 * CHUNK_LEN / 8) {
+            big.push_str(&format!("\n{}\n", i % 1000));
+        }
+        assert_eq!(
+            top_k_totals(io::BufReader::new(big.as_bytes()), 2).unwrap(),
+            [CHUNK_LEN as i64, 999]
+        );
+    }
 }
@@ -1,120 +1,185 @@
-#[derive(PartialEq, Eq, Clone, Copy)]
-pub enum Weapon {
-    Rock,
-    Paper,
-    Scissors,
-}
+use itertools::Itertools;
 
-impl Weapon {
-    fn value(&self) -> i8 {
-        match &self {
-            Weapon::Rock => 0,
-            Weapon::Paper => 1,
-            Weapon::Scissors => 2,
-        }
-    }
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
 }
 
-impl From<i8> for Weapon {
-    fn from(v: i8) -> Self {
-        match v {
-            0 => Weapon::Rock,
-            1 => Weapon::Paper,
-            2 => Weapon::Scissors,
-            _ => panic!("invalid value {}", v),
-        }
-    }
+// A generalisation of the game to any odd number of weapons with cyclic dominance, where
+// each weapon beats the (n - 1) / 2 weapons before it (modulo n) and loses to the rest.
+// Weapons are identified by their index, and their symbols are looked up by position.
+pub struct RuleSet {
+    pub weapons: usize,
+    pub their_symbols: Vec<char>,
+    // the first interpretation: the second column is the weapon we play
+    pub our_symbols: Vec<char>,
+    // the second interpretation: the second column is the outcome, where the i'th symbol
+    // means playing the weapon i - (n - 1) / 2 steps around the cycle from theirs,
+    // so the symbols before the middle lose, the middle one draws and the rest win
+    pub outcome_symbols: Vec<char>,
+    pub weapon_scores: Vec<u32>,
+    pub win: u32,
+    pub draw: u32,
+    pub loss: u32,
 }
 
-#[derive(Clone, Copy)]
-pub struct Play {
-    theirs: Weapon,
-    ours: Weapon,
+pub type Guide = Vec<(char, char)>;
+pub type Input = Guide;
+
+pub fn parse_guide(input: &str) -> Guide {
+    input
+        .lines()
+        .map(|line| {
+            let mut chars = line.chars();
+            let theirs = chars.next().expect("expected line to be longer");
+            let second = chars.nth(1).expect("expected line to be longer");
+            (theirs, second)
+        })
+        .collect()
 }
 
-pub enum Outcome {
-    Win,
-    Loss,
-    Draw,
+fn symbol_idx(symbols: &[char], c: char) -> usize {
+    symbols
+        .iter()
+        .position(|&s| s == c)
+        .unwrap_or_else(|| panic!("Unexpected input: {}", c))
 }
-pub type Input = Vec<Play>;
-
-impl From<char> for Weapon {
-    fn from(c: char) -> Self {
-        match c {
-            'A' | 'X' => Weapon::Rock,
-            'B' | 'Y' => Weapon::Paper,
-            'C' | 'Z' => Weapon::Scissors,
-            c => panic!("Unexpected input: {}", c),
+
+impl RuleSet {
+    // The weapons score 1, 2, 3... and outcomes are scored the same as the original game.
+    pub fn cyclic(weapons: usize, their_symbols: &str, our_symbols: &str) -> Self {
+        assert!(weapons % 2 == 1, "the number of weapons must be odd");
+        let their_symbols = their_symbols.chars().collect::<Vec<_>>();
+        let our_symbols = our_symbols.chars().collect::<Vec<_>>();
+        assert_eq!(their_symbols.len(), weapons, "expected a symbol per weapon");
+        assert_eq!(our_symbols.len(), weapons, "expected a symbol per weapon");
+        RuleSet {
+            weapons,
+            their_symbols,
+            outcome_symbols: our_symbols.clone(),
+            our_symbols,
+            weapon_scores: (1..=weapons as u32).collect(),
+            win: 6,
+            draw: 3,
+            loss: 0,
         }
     }
-}
 
-impl From<&str> for Play {
-    fn from(s: &str) -> Self {
-        Play {
-            theirs: s.chars().next().expect("expected line to be longer").into(),
-            ours: s.chars().nth(2).expect("expected line to be longer").into(),
-        }
+    pub fn rock_paper_scissors() -> Self {
+        Self::cyclic(3, "ABC", "XYZ")
     }
-}
 
-impl Play {
-    fn outcome(&self) -> Outcome {
-        if self.ours == self.theirs {
-            Outcome::Draw
-        } else if (self.theirs.value() + 1) % 3 == self.ours.value() {
-            Outcome::Win
-        } else {
-            Outcome::Loss
-        }
+    // Rock, Spock, Paper, Lizard, Scissors is the order that makes the dominance cyclic.
+    pub fn rock_paper_scissors_lizard_spock() -> Self {
+        Self::cyclic(5, "ABCDE", "VWXYZ")
     }
 
-    fn score_outcome(outcome: Outcome) -> u32 {
-        match outcome {
-            Outcome::Win => 6,
-            Outcome::Draw => 3,
-            Outcome::Loss => 0,
+    pub fn outcome(&self, theirs: usize, ours: usize) -> Outcome {
+        match (ours + self.weapons - theirs) % self.weapons {
+            0 => Outcome::Draw,
+            d if d <= self.weapons / 2 => Outcome::Win,
+            _ => Outcome::Loss,
         }
     }
 
-    fn score_ours(&self) -> u32 {
-        match &self.ours {
-            Weapon::Rock => 1,
-            Weapon::Paper => 2,
-            Weapon::Scissors => 3,
-        }
+    pub fn score(&self, theirs: usize, ours: usize) -> u32 {
+        let outcome = match self.outcome(theirs, ours) {
+            Outcome::Win => self.win,
+            Outcome::Draw => self.draw,
+            Outcome::Loss => self.loss,
+        };
+        outcome + self.weapon_scores[ours]
     }
 
-    fn score(&self) -> u32 {
-        Play::score_outcome(self.outcome()) + self.score_ours()
+    pub fn score_moves(&self, guide: &Guide) -> u32 {
+        guide
+            .iter()
+            .map(|&(theirs, ours)| {
+                self.score(
+                    symbol_idx(&self.their_symbols, theirs),
+                    symbol_idx(&self.our_symbols, ours),
+                )
+            })
+            .sum()
     }
 
-    fn score_p2(&self) -> u32 {
-        let offset: i8 = match &self.ours {
-            Weapon::Rock => -1,
-            Weapon::Paper => 0,
-            Weapon::Scissors => 1,
-        };
-        let our_play = Weapon::from((offset + self.theirs.value()).rem_euclid(3));
-        Play {
-            ours: our_play,
-            theirs: self.theirs,
+    pub fn score_outcomes(&self, guide: &Guide) -> u32 {
+        guide
+            .iter()
+            .map(|&(theirs, outcome)| {
+                let theirs = symbol_idx(&self.their_symbols, theirs);
+                let offset = symbol_idx(&self.outcome_symbols, outcome);
+                let ours = (theirs + offset + self.weapons - self.weapons / 2) % self.weapons;
+                self.score(theirs, ours)
+            })
+            .sum()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reading {
+    Moves,
+    Outcomes,
+}
+
+// One way of reading the second column, where `symbols[i]` means the i'th weapon
+// or the i'th outcome (in the same order as `RuleSet::outcome_symbols`).
+#[derive(Debug, PartialEq, Eq)]
+pub struct Interpretation {
+    pub reading: Reading,
+    pub symbols: Vec<char>,
+    pub score: u32,
+}
+
+impl RuleSet {
+    // Scores the guide under every bijection from the second column's symbols to weapons,
+    // and to outcomes.
+    pub fn interpretations(&self, guide: &Guide) -> Vec<Interpretation> {
+        let mut interpretations = vec![];
+        for symbols in self.our_symbols.iter().copied().permutations(self.weapons) {
+            let rules = RuleSet {
+                our_symbols: symbols.clone(),
+                outcome_symbols: symbols.clone(),
+                their_symbols: self.their_symbols.clone(),
+                weapon_scores: self.weapon_scores.clone(),
+                ..*self
+            };
+            interpretations.push(Interpretation {
+                reading: Reading::Moves,
+                symbols: symbols.clone(),
+                score: rules.score_moves(guide),
+            });
+            interpretations.push(Interpretation {
+                reading: Reading::Outcomes,
+                symbols,
+                score: rules.score_outcomes(guide),
+            });
         }
-        .score()
+        interpretations
     }
 }
 
+// The interpretation with the score closest to the target, preferring the earliest on ties.
+pub fn closest_interpretation(
+    interpretations: &[Interpretation],
+    target: u32,
+) -> Option<&Interpretation> {
+    interpretations
+        .iter()
+        .min_by_key(|i| i.score.abs_diff(target))
+}
+
 pub fn input_generator(input: &str) -> Input {
-    input.lines().map(|line| line.into()).collect()
+    parse_guide(input)
 }
 
 pub fn part_1(input: &Input) -> u32 {
-    input.iter().map(Play::score).sum()
+    RuleSet::rock_paper_scissors().score_moves(input)
 }
 
 pub fn part_2(input: &Input) -> u32 {
-    input.iter().map(Play::score_p2).sum()
+    RuleSet::rock_paper_scissors().score_outcomes(input)
 }
 
 #[cfg(test)]
@@ -127,4 +192,70 @@ mod tests {
         assert_eq!(part_1(&input), 15);
         assert_eq!(part_2(&input), 12);
     }
+
+    #[test]
+    fn test_rule_set() {
+        let guide = parse_guide("A Y\nB X\nC Z");
+        let rules = RuleSet::rock_paper_scissors();
+        assert_eq!(rules.score_moves(&guide), 15);
+        assert_eq!(rules.score_outcomes(&guide), 12);
+
+        let rules = RuleSet {
+            win: 10,
+            weapon_scores: vec![0, 0, 0],
+            ..RuleSet::rock_paper_scissors()
+        };
+        assert_eq!(rules.score_moves(&guide), 13);
+
+        let rules = RuleSet {
+            their_symbols: vec!['R', 'P', 'S'],
+            our_symbols: vec!['r', 'p', 's'],
+            ..RuleSet::rock_paper_scissors()
+        };
+        assert_eq!(rules.score_moves(&parse_guide("R p\nP r\nS s")), 15);
+
+        // rock, spock, paper, lizard, scissors
+        let rules = RuleSet::rock_paper_scissors_lizard_spock();
+        let beats = |a, b| matches!(rules.outcome(b, a), Outcome::Win);
+        assert!(beats(1, 0) && beats(1, 4));
+        assert!(beats(3, 1) && beats(3, 2));
+        assert!(beats(0, 4) && beats(0, 3));
+        assert!(!beats(0, 1) && !beats(0, 2) && !beats(0, 0));
+        let guide = parse_guide("B W\nC V\nD Z\nE Y");
+        // spock draws with spock, rock loses to paper, scissors beats lizard, lizard loses to scissors
+        assert_eq!(rules.score_moves(&guide), 5 + 1 + 11 + 4);
+        // every line happens to call for rock: losing to spock and paper, beating lizard and scissors
+        assert_eq!(rules.score_outcomes(&guide), 1 + 1 + 7 + 7);
+    }
+
+    #[test]
+    fn test_interpretations() {
+        let guide = parse_guide("A Y\nB X\nC Z");
+        let interpretations = RuleSet::rock_paper_scissors().interpretations(&guide);
+        assert_eq!(interpretations.len(), 12);
+
+        let xyz = vec!['X', 'Y', 'Z'];
+        let find = |reading, symbols: &[char]| {
+            interpretations
+                .iter()
+                .find(|i| i.reading == reading && i.symbols == symbols)
+                .unwrap()
+                .score
+        };
+        assert_eq!(find(Reading::Moves, &xyz), 15);
+        assert_eq!(find(Reading::Outcomes, &xyz), 12);
+        // Z is rock, Y is paper and X is scissors: every round is a win
+        assert_eq!(
+            find(Reading::Moves, &['Z', 'Y', 'X']),
+            6 + 2 + 6 + 3 + 6 + 1
+        );
+
+        let closest = closest_interpretation(&interpretations, 15).unwrap();
+        assert_eq!(closest.score, 15);
+        let closest = closest_interpretation(&interpretations, 1000).unwrap();
+        assert_eq!(
+            closest.score,
+            interpretations.iter().map(|i| i.score).max().unwrap()
+        );
+    }
 }
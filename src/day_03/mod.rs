@@ -1,7 +1,6 @@
-use arrayvec::ArrayVec;
-use itertools::Itertools;
+use rustc_hash::FxHashSet;
 
-pub type Rucksack = ArrayVec<u8, 48>;
+pub type Rucksack = Vec<u8>;
 pub type Input = Vec<Rucksack>;
 
 #[derive(Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash)]
@@ -12,22 +11,240 @@ where
 {
     fn from(c: T) -> Self {
         let mut chars = 0;
-        c.for_each(|b| chars |= 1 << (b - b'A'));
+        c.for_each(|&b| {
+            assert!(
+                AsciiCharset::covers(b),
+                "byte {b} doesn't fit in an AsciiCharset"
+            );
+            chars |= 1 << (b - b'A')
+        });
         Self(chars)
     }
 }
 
 impl AsciiCharset {
+    // Only letters are stored, even though the bits between `Z` and `a` are free.
+    pub fn covers(b: u8) -> bool {
+        b.is_ascii_alphabetic()
+    }
+
     fn intersect(&self, other: Self) -> Self {
         AsciiCharset(self.0 & other.0)
     }
 
     fn first(&self) -> Option<u8> {
-        (b'A'..=b'z').find(|&b| self.0 & (1 << (b - b'A')) != 0)
+        self.items().next()
+    }
+
+    fn items(&self) -> impl Iterator<Item = u8> + '_ {
+        (b'A'..=b'z').filter(|&b| self.0 & (1 << (b - b'A')) != 0)
+    }
+}
+
+// A set of rucksack items. `AsciiCharset` is the fast path for the puzzle's letters,
+// `ByteSet` handles any byte and `CharSet` handles any unicode `char`.
+pub trait ItemSet {
+    type Item: Copy + PartialEq;
+
+    fn from_items(items: &[Self::Item]) -> Self;
+    fn intersect(&self, other: &Self) -> Self;
+    // The smallest item in the set.
+    fn first(&self) -> Option<Self::Item>;
+    // Every item in the set, smallest first.
+    fn items(&self) -> Vec<Self::Item>;
+}
+
+impl ItemSet for AsciiCharset {
+    type Item = u8;
+
+    fn from_items(items: &[u8]) -> Self {
+        items.iter().into()
+    }
+
+    fn intersect(&self, other: &Self) -> Self {
+        AsciiCharset::intersect(self, *other)
+    }
+
+    fn first(&self) -> Option<u8> {
+        AsciiCharset::first(self)
+    }
+
+    fn items(&self) -> Vec<u8> {
+        AsciiCharset::items(self).collect()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ByteSet([u64; 4]);
+
+impl ItemSet for ByteSet {
+    type Item = u8;
+
+    fn from_items(items: &[u8]) -> Self {
+        let mut words = [0; 4];
+        items
+            .iter()
+            .for_each(|&b| words[b as usize / 64] |= 1 << (b % 64));
+        Self(words)
+    }
+
+    fn intersect(&self, other: &Self) -> Self {
+        Self(std::array::from_fn(|i| self.0[i] & other.0[i]))
+    }
+
+    fn first(&self) -> Option<u8> {
+        self.0
+            .iter()
+            .enumerate()
+            .find(|(_, &w)| w != 0)
+            .map(|(i, w)| (i * 64) as u8 + w.trailing_zeros() as u8)
+    }
+
+    fn items(&self) -> Vec<u8> {
+        (0..=u8::MAX)
+            .filter(|&b| self.0[b as usize / 64] & (1 << (b % 64)) != 0)
+            .collect()
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct CharSet(FxHashSet<char>);
+
+impl ItemSet for CharSet {
+    type Item = char;
+
+    fn from_items(items: &[char]) -> Self {
+        Self(items.iter().copied().collect())
+    }
+
+    fn intersect(&self, other: &Self) -> Self {
+        Self(self.0.intersection(&other.0).copied().collect())
     }
+
+    fn first(&self) -> Option<char> {
+        self.0.iter().min().copied()
+    }
+
+    fn items(&self) -> Vec<char> {
+        let mut items = self.0.iter().copied().collect::<Vec<_>>();
+        items.sort_unstable();
+        items
+    }
+}
+
+pub fn shared_priority_sum<S, R, P>(rucksacks: &[R], priority: P) -> u32
+where
+    S: ItemSet,
+    R: AsRef<[S::Item]>,
+    P: Fn(S::Item) -> u32,
+{
+    rucksacks
+        .iter()
+        .map(|r| {
+            let (left, right) = r.as_ref().split_at(r.as_ref().len() / 2);
+            let common = S::from_items(left)
+                .intersect(&S::from_items(right))
+                .first()
+                .expect("at least one item should be in both compartments");
+            priority(common)
+        })
+        .sum()
+}
+
+pub fn badge_priority_sum<S, R, P>(rucksacks: &[R], group_size: usize, priority: P) -> u32
+where
+    S: ItemSet,
+    R: AsRef<[S::Item]>,
+    P: Fn(S::Item) -> u32,
+{
+    rucksacks
+        .chunks(group_size)
+        .map(|group| {
+            let badge = group
+                .iter()
+                .map(|r| S::from_items(r.as_ref()))
+                .reduce(|acum, item| acum.intersect(&item))
+                .and_then(|common| common.first())
+                .expect("every group should have a badge");
+            priority(badge)
+        })
+        .sum()
 }
 
-fn priority(b: u8) -> u32 {
+fn count<T: PartialEq>(items: &[T], item: T) -> usize {
+    items.iter().filter(|&b| *b == item).count()
+}
+
+// An item found in both compartments of a rucksack, with the number of copies in each.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SharedItem<T = u8> {
+    pub item: T,
+    pub left: usize,
+    pub right: usize,
+}
+
+pub fn shared_items<S: ItemSet>(rucksack: &[S::Item]) -> Vec<SharedItem<S::Item>> {
+    let (left, right) = rucksack.split_at(rucksack.len() / 2);
+    S::from_items(left)
+        .intersect(&S::from_items(right))
+        .items()
+        .into_iter()
+        .map(|item| SharedItem {
+            item,
+            left: count(left, item),
+            right: count(right, item),
+        })
+        .collect()
+}
+
+// An item carried by every elf in a group, with the number of copies each elf has.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Badge<T = u8> {
+    pub item: T,
+    pub counts: Vec<usize>,
+}
+
+pub struct Group<T = u8> {
+    pub elves: usize,
+    pub badges: Vec<Badge<T>>,
+}
+
+impl<T> Group<T> {
+    // The puzzle assumes every group is full-sized and has exactly one badge.
+    pub fn is_valid(&self, size: usize) -> bool {
+        self.elves == size && self.badges.len() == 1
+    }
+}
+
+pub fn groups<S, R>(input: &[R], size: usize) -> Vec<Group<S::Item>>
+where
+    S: ItemSet,
+    R: AsRef<[S::Item]>,
+{
+    input
+        .chunks(size)
+        .map(|group| {
+            let common = group
+                .iter()
+                .map(|r| S::from_items(r.as_ref()))
+                .reduce(|acum, item| acum.intersect(&item))
+                .unwrap();
+            Group {
+                elves: group.len(),
+                badges: common
+                    .items()
+                    .into_iter()
+                    .map(|item| Badge {
+                        item,
+                        counts: group.iter().map(|r| count(r.as_ref(), item)).collect(),
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+pub fn priority(b: u8) -> u32 {
     let priority = match b {
         b'a'..=b'z' => b - b'a' + 1,
         b'A'..=b'Z' => b - b'A' + 27,
@@ -40,31 +257,25 @@ pub fn input_generator(input: &str) -> Input {
     input.lines().map(|line| line.bytes().collect()).collect()
 }
 
+// The letters fit in an `AsciiCharset`, which is the fast path, but any other byte needs a `ByteSet`.
+fn is_ascii_letters(input: &Input) -> bool {
+    input.iter().flatten().all(|&b| AsciiCharset::covers(b))
+}
+
 pub fn part_1(input: &Input) -> u32 {
-    input
-        .iter()
-        .map(|r| {
-            let left: AsciiCharset = r.into_iter().take(r.len() / 2).into();
-            let right: AsciiCharset = r.into_iter().skip(r.len() / 2).into();
-            let common = left
-                .intersect(right)
-                .first()
-                .expect("at least one item should be in both compartments");
-            priority(common)
-        })
-        .sum()
+    if is_ascii_letters(input) {
+        shared_priority_sum::<AsciiCharset, _, _>(input, priority)
+    } else {
+        shared_priority_sum::<ByteSet, _, _>(input, priority)
+    }
 }
 
 pub fn part_2(input: &Input) -> u32 {
-    let mut sum = 0;
-    for chunk in &input.iter().chunks(3) {
-        sum += chunk
-            .map(|r| AsciiCharset::from(r.into_iter()))
-            .reduce(|acum, item| acum.intersect(item))
-            .map(|c| priority(c.first().unwrap()))
-            .unwrap();
+    if is_ascii_letters(input) {
+        badge_priority_sum::<AsciiCharset, _, _>(input, 3, priority)
+    } else {
+        badge_priority_sum::<ByteSet, _, _>(input, 3, priority)
     }
-    sum
 }
 
 #[cfg(test)]
@@ -85,4 +296,138 @@ CrZsJsPPZsGzwwsLwLmpwMDw",
         assert_eq!(part_1(&input), 157);
         assert_eq!(part_2(&input), 70);
     }
+
+    #[test]
+    fn test_alphabets() {
+        let bytes = [
+            vec![b'1', b'{', 0xff, b'{', b'2', b'3'],
+            vec![0x01, 0xff, b'z', b'y', 0x01, b'x'],
+            vec![0x7f, 0xff, b'~', b'~', b'q', b'r'],
+        ];
+        let value = |b: u8| b as u32;
+        assert_eq!(
+            shared_priority_sum::<ByteSet, _, _>(&bytes, value),
+            b'{' as u32 + 0x01 + b'~' as u32
+        );
+        assert_eq!(badge_priority_sum::<ByteSet, _, _>(&bytes, 3, value), 0xff);
+
+        let chars = ["αβγα", "δεζδ", "αδεα"].map(|l| l.chars().collect::<Vec<_>>());
+        let greek = |c: char| c as u32 - 'α' as u32 + 1;
+        assert_eq!(
+            shared_priority_sum::<CharSet, _, _>(&chars, greek),
+            1 + 4 + 1
+        );
+        assert_eq!(
+            badge_priority_sum::<CharSet, _, _>(&chars[1..], 2, greek),
+            4
+        );
+    }
+
+    #[test]
+    fn test_shared_items() {
+        let input = input_generator("vJrwpWtwJgWrhcsFMMfFFhFp\naabBcAaAbc");
+        assert_eq!(
+            shared_items::<AsciiCharset>(&input[0]),
+            [SharedItem {
+                item: b'p',
+                left: 1,
+                right: 1
+            }]
+        );
+        let shared = shared_items::<AsciiCharset>(&input[1]);
+        let counts = shared
+            .iter()
+            .map(|s| (s.item, s.left, s.right))
+            .collect::<Vec<_>>();
+        assert_eq!(counts, [(b'a', 2, 1), (b'b', 1, 1), (b'c', 1, 1)]);
+    }
+
+    #[test]
+    fn test_groups() {
+        let input = input_generator("abcA\nbcdA\nbceA\nxyz\nxyq");
+        let threes = groups::<AsciiCharset, _>(&input, 3);
+        assert_eq!(threes.len(), 2);
+        assert_eq!(
+            threes[0].badges,
+            [
+                Badge {
+                    item: b'A',
+                    counts: vec![1, 1, 1]
+                },
+                Badge {
+                    item: b'b',
+                    counts: vec![1, 1, 1]
+                },
+                Badge {
+                    item: b'c',
+                    counts: vec![1, 1, 1]
+                },
+            ]
+        );
+        assert!(!threes[0].is_valid(3));
+        assert_eq!(threes[1].elves, 2);
+        assert!(!threes[1].is_valid(3));
+
+        let pairs = groups::<AsciiCharset, _>(&input_generator("abc\ncde\nxy\nyz"), 2);
+        assert!(pairs.iter().all(|g| g.is_valid(2)));
+
+        let fives = groups::<AsciiCharset, _>(&input, 5);
+        assert_eq!(fives.len(), 1);
+        assert!(fives[0].badges.is_empty());
+    }
+
+    #[test]
+    fn test_other_items() {
+        // bytes outside the letters fall back to a `ByteSet` instead of overflowing
+        let input = input_generator("1ab2ac\n#x!#y!\na3\n4a");
+        assert!(!is_ascii_letters(&input));
+        assert_eq!(part_1(&input[..1].to_vec()), 1);
+        // including the ones between the upper and lower case letters
+        let input_between = input_generator("[ab]ac");
+        assert!(!is_ascii_letters(&input_between));
+        assert_eq!(part_1(&input_between), 1);
+        assert!(is_ascii_letters(&input_generator("AZaz")));
+        assert_eq!(
+            part_2(&vec![input[0].clone(), input[2].clone(), input[3].clone()]),
+            1
+        );
+        assert_eq!(
+            shared_items::<ByteSet>(&input[1]),
+            [
+                SharedItem {
+                    item: b'!',
+                    left: 1,
+                    right: 1
+                },
+                SharedItem {
+                    item: b'#',
+                    left: 1,
+                    right: 1
+                }
+            ]
+        );
+
+        let chars = ["αβγα", "δαζδ"].map(|l| l.chars().collect::<Vec<_>>());
+        assert_eq!(
+            shared_items::<CharSet>(&chars[0]),
+            [SharedItem {
+                item: 'α',
+                left: 1,
+                right: 1
+            }]
+        );
+        let pair = groups::<CharSet, _>(&chars, 2);
+        assert!(pair[0].is_valid(2));
+        assert_eq!(
+            pair[0].badges,
+            [Badge {
+                item: 'α',
+                counts: vec![2, 1]
+            }]
+        );
+
+        // more items than the puzzle's rucksacks ever hold
+        let big = input_generator(&format!("{}z{}z", "a".repeat(100), "b".repeat(100)));
+        assert_eq!(part_1(&big), 26);
+    }
 }
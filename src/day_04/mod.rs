@@ -2,8 +2,10 @@ use std::ops::RangeInclusive;
 
 use itertools::Itertools;
 
+pub type Section = u32;
+
 pub struct Assignment {
-    range: RangeInclusive<u8>,
+    range: RangeInclusive<Section>,
 }
 pub struct Pair {
     left: Assignment,
@@ -20,9 +22,10 @@ pub fn input_generator(input: &str) -> Input {
                 .map(|p| {
                     let (min, max) = p
                         .split('-')
-                        .map(|n| n.parse::<u8>().expect("couldn't parse as u8"))
+                        .map(|n| n.parse::<Section>().expect("couldn't parse section"))
                         .collect_tuple()
                         .expect("two '-' separated ints are expected");
+                    assert!(min <= max, "reversed assignment {p}");
                     min..=max
                 })
                 .collect_tuple()
@@ -45,6 +48,171 @@ impl Assignment {
     }
 }
 
+// An index over any number of assignments for answering coverage queries.
+// The assignments are sorted by start and laid out as an implicit balanced tree
+// (the root of each sub-slice is its middle element), where each node knows the
+// largest end in its subtree - i.e. a static interval tree.
+pub struct Coverage {
+    // (start, end, elf) sorted by start
+    assignments: Vec<(Section, Section, usize)>,
+    max_ends: Vec<Section>,
+    // (start, end) of each elf's assignment
+    by_elf: Vec<(Section, Section)>,
+    // the number of elves covering each section from the boundary up to the next one
+    segments: Vec<(u64, usize)>,
+}
+
+fn build_max_ends(assignments: &[(Section, Section, usize)], max_ends: &mut [Section]) -> Section {
+    if assignments.is_empty() {
+        return 0;
+    }
+    let mid = assignments.len() / 2;
+    let left = build_max_ends(&assignments[..mid], &mut max_ends[..mid]);
+    let right = build_max_ends(&assignments[mid + 1..], &mut max_ends[mid + 1..]);
+    max_ends[mid] = assignments[mid].1.max(left).max(right);
+    max_ends[mid]
+}
+
+fn find_covering(
+    assignments: &[(Section, Section, usize)],
+    max_ends: &[Section],
+    section: Section,
+    out: &mut Vec<usize>,
+) {
+    if assignments.is_empty() {
+        return;
+    }
+    let mid = assignments.len() / 2;
+    if max_ends[mid] < section {
+        return;
+    }
+    find_covering(&assignments[..mid], &max_ends[..mid], section, out);
+    let (start, end, elf) = assignments[mid];
+    if start <= section {
+        if end >= section {
+            out.push(elf);
+        }
+        find_covering(&assignments[mid + 1..], &max_ends[mid + 1..], section, out);
+    }
+}
+
+impl Coverage {
+    // Elves are numbered by their position in `assignments`, which must not be reversed.
+    pub fn new<I: IntoIterator<Item = RangeInclusive<Section>>>(assignments: I) -> Self {
+        let by_elf = assignments
+            .into_iter()
+            .map(|r| {
+                assert!(r.start() <= r.end(), "reversed assignment {r:?}");
+                (*r.start(), *r.end())
+            })
+            .collect::<Vec<_>>();
+        let mut assignments = by_elf
+            .iter()
+            .enumerate()
+            .map(|(elf, &(start, end))| (start, end, elf))
+            .collect::<Vec<_>>();
+        assignments.sort_unstable();
+
+        let mut max_ends = vec![0; assignments.len()];
+        build_max_ends(&assignments, &mut max_ends);
+
+        // sweep over the boundaries, using u64 so that an end of `Section::MAX` doesn't overflow
+        let mut events = assignments
+            .iter()
+            .flat_map(|&(start, end, _)| [(start as u64, 1), (end as u64 + 1, -1)])
+            .collect::<Vec<_>>();
+        events.sort_unstable();
+        let mut segments: Vec<(u64, usize)> = vec![];
+        let mut count = 0isize;
+        for (i, &(pos, delta)) in events.iter().enumerate() {
+            count += delta;
+            // wait for every event at this boundary, and only start a segment if the count changes
+            if events.get(i + 1).is_some_and(|&(next, _)| next == pos) {
+                continue;
+            }
+            if segments.last().map(|s| s.1) != Some(count as usize) {
+                segments.push((pos, count as usize));
+            }
+        }
+
+        Self {
+            assignments,
+            max_ends,
+            by_elf,
+            segments,
+        }
+    }
+
+    // Each pair is two elves, so pair `i` is elves `2 * i` and `2 * i + 1`.
+    pub fn from_pairs(input: &Input) -> Self {
+        Self::new(
+            input
+                .iter()
+                .flat_map(|p| [p.left.range.clone(), p.right.range.clone()]),
+        )
+    }
+
+    // The elves whose assignments include the section, in ascending order.
+    pub fn covering(&self, section: Section) -> Vec<usize> {
+        let mut elves = vec![];
+        find_covering(&self.assignments, &self.max_ends, section, &mut elves);
+        elves.sort_unstable();
+        elves
+    }
+
+    // Every maximal range of sections with the same non-zero number of elves covering it.
+    pub fn counts(&self) -> impl Iterator<Item = (RangeInclusive<Section>, usize)> + '_ {
+        self.segments
+            .windows(2)
+            .filter(|w| w[0].1 > 0)
+            .map(|w| (w[0].0 as Section..=(w[1].0 - 1) as Section, w[0].1))
+    }
+
+    // The most elves covering any one section, and the ranges of sections with that many.
+    pub fn most_covered(&self) -> (usize, Vec<RangeInclusive<Section>>) {
+        let most = self.counts().map(|(_, c)| c).max().unwrap_or(0);
+        let ranges = self
+            .counts()
+            .filter(|&(_, c)| c == most)
+            .map(|(r, _)| r)
+            .collect();
+        (most, ranges)
+    }
+
+    // The ranges of sections within `bounds` that no elf has been assigned.
+    pub fn uncovered(&self, bounds: RangeInclusive<Section>) -> Vec<RangeInclusive<Section>> {
+        if bounds.is_empty() {
+            return vec![];
+        }
+        let mut gaps = vec![];
+        let mut next = *bounds.start() as u64;
+        let end = *bounds.end() as u64;
+        for (range, _) in self.counts() {
+            let (start, stop) = (*range.start() as u64, *range.end() as u64);
+            if start > next {
+                gaps.push(next as Section..=(start - 1).min(end) as Section);
+            }
+            next = next.max(stop + 1);
+            if next > end {
+                return gaps;
+            }
+        }
+        gaps.push(next as Section..=end as Section);
+        gaps
+    }
+
+    // The sections shared by all of the given elves, if any. `None` if any of them don't exist.
+    pub fn overlap(&self, elves: &[usize]) -> Option<RangeInclusive<Section>> {
+        let ranges = elves
+            .iter()
+            .map(|&elf| self.by_elf.get(elf))
+            .collect::<Option<Vec<_>>>()?;
+        let start = ranges.iter().map(|r| r.0).max()?;
+        let end = ranges.iter().map(|r| r.1).min()?;
+        (start <= end).then_some(start..=end)
+    }
+}
+
 pub fn part_1(input: &Input) -> usize {
     input
         .iter()
@@ -74,4 +242,60 @@ mod tests {
         assert_eq!(part_1(&input), 2);
         assert_eq!(part_2(&input), 4);
     }
+
+    #[test]
+    fn test_coverage() {
+        let input = input_generator(
+            "\
+2-4,6-8
+2-3,4-5
+5-7,7-9
+2-8,3-7
+6-6,4-6
+2-6,4-8",
+        );
+        let coverage = Coverage::from_pairs(&input);
+        assert_eq!(coverage.covering(1), []);
+        assert_eq!(coverage.covering(2), [0, 2, 6, 10]);
+        assert_eq!(coverage.covering(9), [5]);
+        assert_eq!(coverage.most_covered(), (8, vec![6..=6]));
+        assert_eq!(coverage.uncovered(0..=12), [0..=1, 10..=12]);
+        assert_eq!(coverage.uncovered(3..=5), []);
+        assert_eq!(coverage.overlap(&[6, 7, 10]), Some(3..=6));
+        assert_eq!(coverage.overlap(&[0, 1]), None);
+
+        let coverage = Coverage::new([300..=Section::MAX, 1000..=1000, 0..=10]);
+        assert_eq!(coverage.covering(1000), [0, 1]);
+        assert_eq!(coverage.covering(Section::MAX), [0]);
+        assert_eq!(coverage.uncovered(0..=Section::MAX), [11..=299]);
+        assert_eq!(coverage.most_covered(), (2, vec![1000..=1000]));
+
+        // touching assignments make one range, and the count can go back to the same value
+        let coverage = Coverage::new([0..=5, 6..=10, 3..=3, 20..=21]);
+        assert_eq!(
+            coverage.counts().collect_vec(),
+            [(0..=2, 1), (3..=3, 2), (4..=10, 1), (20..=21, 1)]
+        );
+        assert_eq!(coverage.most_covered(), (2, vec![3..=3]));
+        let coverage = Coverage::new([0..=5, 6..=10]);
+        assert_eq!(coverage.most_covered(), (1, vec![0..=10]));
+        assert_eq!(coverage.overlap(&[0]), Some(0..=5));
+        assert_eq!(coverage.overlap(&[0, 99]), None);
+        assert_eq!(coverage.overlap(&[]), None);
+        let (start, end) = (20, 3);
+        assert_eq!(coverage.uncovered(start..=end), []);
+    }
+
+    #[test]
+    #[should_panic(expected = "reversed assignment 5-3")]
+    fn test_reversed_input() {
+        input_generator("5-3,1-2");
+    }
+
+    #[test]
+    #[should_panic(expected = "reversed assignment 5..=3")]
+    fn test_reversed_coverage() {
+        let (start, end) = (5, 3);
+        Coverage::new([start..=end, 1..=2]);
+    }
 }
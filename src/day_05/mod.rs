@@ -1,14 +1,93 @@
 use arrayvec::ArrayVec;
 use itertools::Itertools;
 
-pub type Stack = ArrayVec<u8, 64>;
-pub type Stacks = Vec<Stack>;
+pub type Stack<T> = Vec<T>;
 
-#[derive(Clone, Copy)]
+// Crates can have labels of any length, but they're almost always a single character, so those
+// are stored a byte per crate and owned strings are only used once a longer label turns up.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Stacks {
+    Bytes(Vec<Stack<u8>>),
+    Labels(Vec<Stack<Box<str>>>),
+}
+
+pub trait Label: Clone {
+    fn label(&self) -> &str;
+}
+
+impl Label for u8 {
+    fn label(&self) -> &str {
+        // only ASCII labels are stored as bytes
+        std::str::from_utf8(std::slice::from_ref(self)).unwrap()
+    }
+}
+
+impl Label for Box<str> {
+    fn label(&self) -> &str {
+        self
+    }
+}
+
+impl Default for Stacks {
+    fn default() -> Self {
+        Stacks::Bytes(vec![])
+    }
+}
+
+impl Stacks {
+    // Uses a byte per crate if every label is a single byte.
+    pub fn new<S: AsRef<str>>(stacks: Vec<Stack<S>>) -> Self {
+        if stacks.iter().flatten().all(|c| c.as_ref().len() == 1) {
+            Stacks::Bytes(
+                stacks
+                    .iter()
+                    .map(|s| s.iter().map(|c| c.as_ref().as_bytes()[0]).collect())
+                    .collect(),
+            )
+        } else {
+            Stacks::Labels(
+                stacks
+                    .iter()
+                    .map(|s| s.iter().map(|c| c.as_ref().into()).collect())
+                    .collect(),
+            )
+        }
+    }
+
+    // The labels of each stack, from the bottom up.
+    pub fn labels(&self) -> Vec<Vec<&str>> {
+        fn labels<T: Label>(stacks: &[Stack<T>]) -> Vec<Vec<&str>> {
+            stacks
+                .iter()
+                .map(|s| s.iter().map(Label::label).collect())
+                .collect()
+        }
+        match self {
+            Stacks::Bytes(stacks) => labels(stacks),
+            Stacks::Labels(stacks) => labels(stacks),
+        }
+    }
+
+    fn height(&self, stack: usize) -> Option<usize> {
+        match self {
+            Stacks::Bytes(stacks) => stacks.get(stack).map(Vec::len),
+            Stacks::Labels(stacks) => stacks.get(stack).map(Vec::len),
+        }
+    }
+
+    fn count(&self) -> usize {
+        match self {
+            Stacks::Bytes(stacks) => stacks.len(),
+            Stacks::Labels(stacks) => stacks.len(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Instruction {
-    n: u8,
-    from: u8,
-    to: u8,
+    n: usize,
+    from: usize,
+    to: usize,
 }
 
 #[derive(Clone)]
@@ -17,28 +96,46 @@ pub struct Input {
     instructions: Vec<Instruction>,
 }
 
+// The position of the middle of a span of a line, doubled to avoid rounding.
+fn centre(start: usize, len: usize) -> usize {
+    2 * start + len
+}
+
 pub fn input_generator(input: &str) -> Input {
-    let mut stacks = Stacks::new();
-    input
-        .lines()
-        .take_while(|line| !line.starts_with(" 1"))
-        .for_each(|line| {
-            for (i, chunk) in line.chars().chunks(4).into_iter().enumerate() {
-                let c = chunk.into_iter().nth(1).unwrap() as u8;
-                if c == b' ' {
-                    continue;
-                }
-                let stack: &mut Stack = match stacks.get_mut(i) {
-                    Some(stack) => stack,
-                    None => {
-                        stacks.extend((1..=(i + 1 - stacks.len())).map(|_| Stack::new()));
-                        &mut stacks[i]
-                    }
-                };
-                stack.push(c);
-            }
-        });
-    stacks.iter_mut().for_each(|s| s.reverse());
+    let drawing = input.lines().take_while(|l| !l.is_empty()).collect_vec();
+    let (labels, levels) = drawing
+        .split_last()
+        .expect("expected a drawing of the stacks");
+
+    // Each crate belongs to the stack whose label is nearest, which doesn't rely on
+    // the crates and labels all being a single character wide.
+    let label_centres = labels
+        .split(' ')
+        .scan(0, |pos, word| {
+            let start = *pos;
+            *pos += word.len() + 1;
+            Some((start, word.len()))
+        })
+        .filter(|&(_, len)| len > 0)
+        .map(|(start, len)| centre(start, len))
+        .collect_vec();
+    let mut stacks = vec![Stack::<&str>::new(); label_centres.len()];
+    for level in levels.iter().rev() {
+        let mut rest = *level;
+        let mut offset = 0;
+        while let Some(open) = rest.find('[') {
+            let close = open + rest[open..].find(']').expect("unclosed crate");
+            let crate_centre = centre(offset + open, close - open + 1);
+            let (stack, _) = label_centres
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, &c)| c.abs_diff(crate_centre))
+                .expect("crates found without any stack labels");
+            stacks[stack].push(&rest[open + 1..close]);
+            offset += close + 1;
+            rest = &rest[close + 1..];
+        }
+    }
 
     let instructions = input
         .lines()
@@ -47,58 +144,238 @@ pub fn input_generator(input: &str) -> Input {
         .map(|line| {
             let (n, from, to) = line
                 .split_ascii_whitespace()
-                .filter_map(|part| part.parse::<u8>().ok())
+                .filter_map(|part| part.parse::<usize>().ok())
                 .collect_tuple()
                 .expect("Couldn't parse instruction");
+            let index = |stack: usize| stack.checked_sub(1).expect("stacks are numbered from 1");
             Instruction {
                 n,
-                from: from - 1,
-                to: to - 1,
+                from: index(from),
+                to: index(to),
             }
         })
         .collect();
 
     Input {
-        stacks,
+        stacks: Stacks::new(stacks),
         instructions,
     }
 }
 
+// Draws the stacks the same way as the puzzle input, including the trailing spaces.
+// Every column is as wide as the widest crate or stack label, plus a space either side.
+pub fn render_stacks(stacks: &Stacks) -> String {
+    let stacks = stacks.labels();
+    let height = stacks.iter().map(|s| s.len()).max().unwrap_or(0);
+    let widest_crate = stacks.iter().flatten().map(|c| c.len()).max().unwrap_or(1);
+    let width = widest_crate.max(stacks.len().to_string().len()) + 2;
+    let mut out = String::new();
+    for level in (0..height).rev() {
+        let line = stacks
+            .iter()
+            .map(|s| match s.get(level) {
+                Some(c) => format!("{:<width$}", format!("[{c}]")),
+                None => " ".repeat(width),
+            })
+            .join(" ");
+        out.push_str(&line);
+        out.push('\n');
+    }
+    let labels = (1..=stacks.len()).map(|i| format!("{i:^width$}")).join(" ");
+    out.push_str(&labels);
+    out.push('\n');
+    out
+}
+
 fn read_stacks(stacks: &Stacks) -> String {
     stacks
-        .iter()
-        .filter_map(|s| s.last())
-        .map(|c| *c as char)
+        .labels()
+        .into_iter()
+        .filter_map(|s| s.last().copied())
         .collect()
 }
 
-pub fn part_1(input: &Input) -> String {
-    let mut stacks = input.stacks.clone();
-    for Instruction { n, from, to } in &input.instructions {
+#[derive(Debug, PartialEq, Eq)]
+pub enum MoveError {
+    NoSuchStack(usize),
+    NotEnoughCrates { stack: usize, needed: usize },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct IllegalMove {
+    pub instruction: usize,
+    pub error: MoveError,
+}
+
+fn check_move(stacks: &Stacks, Instruction { n, from, to }: &Instruction) -> Result<(), MoveError> {
+    if *to >= stacks.count() {
+        return Err(MoveError::NoSuchStack(*to));
+    }
+    let height = stacks.height(*from).ok_or(MoveError::NoSuchStack(*from))?;
+    if height < *n {
+        return Err(MoveError::NotEnoughCrates {
+            stack: *from,
+            needed: *n,
+        });
+    }
+    Ok(())
+}
+
+pub trait Crane {
+    // Only called with instructions that have passed `check_move`.
+    fn move_crates<T: Clone>(&self, stacks: &mut [Stack<T>], instruction: &Instruction);
+
+    fn move_any(&self, stacks: &mut Stacks, instruction: &Instruction) {
+        match stacks {
+            Stacks::Bytes(stacks) => self.move_crates(stacks, instruction),
+            Stacks::Labels(stacks) => self.move_crates(stacks, instruction),
+        }
+    }
+
+    fn apply(&self, stacks: &mut Stacks, instruction: &Instruction) -> Result<(), MoveError> {
+        check_move(stacks, instruction)?;
+        self.move_any(stacks, instruction);
+        Ok(())
+    }
+
+    // Both cranes undo a move by making the same move in the opposite direction.
+    fn undo(&self, stacks: &mut Stacks, instruction: &Instruction) {
+        let reversed = Instruction {
+            from: instruction.to,
+            to: instruction.from,
+            ..*instruction
+        };
+        self.move_any(stacks, &reversed);
+    }
+}
+
+// Moves crates one at a time.
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn move_crates<T: Clone>(
+        &self,
+        stacks: &mut [Stack<T>],
+        Instruction { n, from, to }: &Instruction,
+    ) {
         (0..*n).for_each(|_| {
-            let from_stack = stacks
-                .get_mut(*from as usize)
-                .expect("from stack not found");
-            let elm = from_stack.pop().expect("stack is empty!");
-            let to_stack = stacks.get_mut(*to as usize).expect("to stack not found");
-            to_stack.push(elm);
+            let elm = stacks[*from].pop().unwrap();
+            stacks[*to].push(elm);
         });
     }
+}
+
+// Moves all the crates at once, keeping their order.
+pub struct CrateMover9001;
+
+const SMALL_MOVE: usize = 32;
+
+impl Crane for CrateMover9001 {
+    fn move_crates<T: Clone>(
+        &self,
+        stacks: &mut [Stack<T>],
+        Instruction { n, from, to }: &Instruction,
+    ) {
+        let from_stack = &mut stacks[*from];
+        let start = from_stack.len() - *n;
+        // avoid allocating for the (usual) small moves
+        if *n <= SMALL_MOVE {
+            let elms: ArrayVec<_, SMALL_MOVE> = from_stack.drain(start..).collect();
+            stacks[*to].extend(elms);
+        } else {
+            let elms = from_stack.split_off(start);
+            stacks[*to].extend(elms);
+        }
+    }
+}
+
+// Steps through the instructions, yielding the stacks after each one.
+// Iteration stops after the first illegal move, until it's undone past.
+pub struct Simulator<'a, C: Crane> {
+    crane: C,
+    stacks: Stacks,
+    instructions: &'a [Instruction],
+    // the number of instructions applied, which is also the index of the next one
+    applied: usize,
+    stopped: bool,
+}
+
+impl<'a, C: Crane> Simulator<'a, C> {
+    pub fn new(crane: C, input: &'a Input) -> Self {
+        Self {
+            crane,
+            stacks: input.stacks.clone(),
+            instructions: &input.instructions,
+            applied: 0,
+            stopped: false,
+        }
+    }
+
+    pub fn stacks(&self) -> &Stacks {
+        &self.stacks
+    }
+
+    // The index of the next instruction to be applied.
+    pub fn position(&self) -> usize {
+        self.applied
+    }
+
+    pub fn apply_next(&mut self) -> Option<Result<&Stacks, IllegalMove>> {
+        if self.stopped {
+            return None;
+        }
+        let instruction = self.instructions.get(self.applied)?;
+        if let Err(error) = self.crane.apply(&mut self.stacks, instruction) {
+            self.stopped = true;
+            return Some(Err(IllegalMove {
+                instruction: self.applied,
+                error,
+            }));
+        }
+        self.applied += 1;
+        Some(Ok(&self.stacks))
+    }
+
+    // Reverts the last applied instruction, returning false if there was nothing to undo.
+    // An illegal move was never applied, so this reverts the one before it.
+    pub fn undo(&mut self) -> bool {
+        if self.applied == 0 {
+            return false;
+        }
+        self.applied -= 1;
+        self.stopped = false;
+        self.crane
+            .undo(&mut self.stacks, &self.instructions[self.applied]);
+        true
+    }
+
+    pub fn run(mut self) -> Result<Stacks, IllegalMove> {
+        while let Some(result) = self.apply_next() {
+            result?;
+        }
+        Ok(self.stacks)
+    }
+}
+
+impl<C: Crane> Iterator for Simulator<'_, C> {
+    type Item = Result<Stacks, IllegalMove>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.apply_next().map(|result| result.cloned())
+    }
+}
+
+pub fn part_1(input: &Input) -> String {
+    let stacks = Simulator::new(CrateMover9000, input)
+        .run()
+        .expect("illegal move");
     read_stacks(&stacks)
 }
 
 pub fn part_2(input: &Input) -> String {
-    let mut stacks = input.stacks.clone();
-    for Instruction { n, from, to } in &input.instructions {
-        let from_stack = stacks
-            .get_mut(*from as usize)
-            .expect("from stack not found");
-        let elms: ArrayVec<_, 32> = from_stack
-            .drain((from_stack.len() - *n as usize)..(from_stack.len()))
-            .collect();
-        let to_stack = stacks.get_mut(*to as usize).expect("to stack not found");
-        to_stack.extend(elms);
-    }
+    let stacks = Simulator::new(CrateMover9001, input)
+        .run()
+        .expect("illegal move");
     read_stacks(&stacks)
 }
 
@@ -122,5 +399,144 @@ mod tests {
             "});
         assert_eq!(part_1(&input), "CMZ");
         assert_eq!(part_2(&input), "MCD");
+        assert_eq!(
+            input.stacks,
+            Stacks::Bytes(vec![b"ZN".to_vec(), b"MCD".to_vec(), b"P".to_vec()])
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let drawing = indoc! {"
+                [D]    
+            [N] [C]    
+            [Z] [M] [P]
+             1   2   3 
+        "};
+        let input = input_generator(drawing);
+        assert_eq!(render_stacks(&input.stacks), drawing);
+        assert_eq!(render_stacks(&Stacks::default()), "\n");
+
+        // round-trip randomly generated stacks (with a simple LCG to avoid a dependency)
+        let mut seed: u64 = 2022;
+        let mut rand = |n: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % n
+        };
+        for _ in 0..200 {
+            let stacks: Vec<Stack<String>> = (0..1 + rand(15))
+                .map(|_| {
+                    (0..rand(80))
+                        .map(|_| {
+                            (0..1 + rand(3))
+                                .map(|_| (b'A' + rand(26) as u8) as char)
+                                .collect()
+                        })
+                        .collect()
+                })
+                .collect();
+            let stacks = Stacks::new(stacks);
+            let drawing = render_stacks(&stacks);
+            assert_eq!(input_generator(&drawing).stacks, stacks, "{drawing}");
+        }
+    }
+
+    #[test]
+    fn test_large() {
+        let input = input_generator(indoc! {"
+                [AB]                                             [K] 
+                [CD]                                             [L] 
+                [EF] [G]  [H]  [I]  [J]  [K]  [L]  [M]  [N]  [O] [P] 
+                 1    2    3    4    5    6    7    8    9    10   11 
+
+                move 1 from 11 to 1
+                move 2 from 1 to 10
+                move 1 from 11 to 2
+            "});
+        assert!(matches!(input.stacks, Stacks::Labels(_)));
+        assert_eq!(read_stacks(&input.stacks), "ABGHIJKLMNOK");
+        assert_eq!(part_1(&input), "CDLHIJKLMNABP");
+        assert_eq!(part_2(&input), "CDLHIJKLMNKP");
+
+        let mut input = input_generator("[A]\n 1 \n\nmove 100 from 1 to 2");
+        input.stacks = Stacks::new(vec![(0..100).map(|i| i.to_string()).collect(), vec![]]);
+        assert_eq!(part_1(&input), "0");
+        assert_eq!(part_2(&input), "99");
+    }
+
+    #[test]
+    fn test_simulator() {
+        let input = input_generator(indoc! {"
+                    [D]    
+                [N] [C]    
+                [Z] [M] [P]
+                 1   2   3 
+
+                move 1 from 2 to 1
+                move 3 from 1 to 3
+                move 2 from 2 to 1
+                move 1 from 1 to 2
+                move 3 from 1 to 2
+                move 1 from 4 to 2
+            "});
+        let tops = Simulator::new(CrateMover9000, &input)
+            .map(|s| s.map(|s| read_stacks(&s)))
+            .collect_vec();
+        assert_eq!(
+            tops,
+            [
+                Ok("DCP".to_string()),
+                Ok("CZ".to_string()),
+                Ok("MZ".to_string()),
+                Ok("CMZ".to_string()),
+                Err(IllegalMove {
+                    instruction: 4,
+                    error: MoveError::NotEnoughCrates {
+                        stack: 0,
+                        needed: 3
+                    }
+                }),
+            ]
+        );
+
+        let mut sim = Simulator::new(CrateMover9001, &input);
+        for _ in 0..4 {
+            sim.apply_next().unwrap().unwrap();
+        }
+        assert_eq!(read_stacks(sim.stacks()), "MCD");
+        while sim.undo() {}
+        assert_eq!(sim.stacks(), &input.stacks);
+        assert_eq!(sim.position(), 0);
+
+        // undoing after an illegal move only reverts the moves that were made
+        let mut sim = Simulator::new(CrateMover9000, &input);
+        while let Some(Ok(_)) = sim.apply_next() {}
+        assert!(sim.apply_next().is_none());
+        assert_eq!(sim.position(), 4);
+        assert!(sim.undo());
+        assert_eq!(sim.position(), 3);
+        assert_eq!(read_stacks(sim.stacks()), "MZ");
+        assert_eq!(read_stacks(sim.apply_next().unwrap().unwrap()), "CMZ");
+        assert!(sim.apply_next().unwrap().is_err());
+        while sim.undo() {}
+        assert_eq!(sim.stacks(), &input.stacks);
+
+        let mut input = input;
+        input.instructions.remove(4);
+        assert_eq!(
+            Simulator::new(CrateMover9001, &input).run(),
+            Err(IllegalMove {
+                instruction: 4,
+                error: MoveError::NoSuchStack(3)
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "stacks are numbered from 1")]
+    fn test_stack_zero() {
+        input_generator("[A]\n 1 \n\nmove 1 from 0 to 1");
     }
 }
@@ -1,55 +1,104 @@
-use std::iter;
-
-use arrayvec::ArrayVec;
-
-#[derive(Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash)]
-pub struct LowerAsciiCharset(u32);
-impl<'a, T> From<T> for LowerAsciiCharset
-where
-    T: Iterator<Item = &'a u8>,
-{
-    fn from(c: T) -> Self {
-        let mut chars = 0;
-        c.for_each(|b| chars |= 1 << (b - b'a'));
-        Self(chars)
+use std::io::{self, Read};
+
+// Finds the markers of any length in a single pass, by tracking where each byte was last
+// seen and the start of the current run of distinct bytes.
+pub struct MarkerDetector {
+    window: usize,
+    // one past the position each byte was last seen at, so 0 means never
+    last_seen: [usize; 256],
+    run_start: usize,
+    pos: usize,
+}
+
+impl MarkerDetector {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            last_seen: [0; 256],
+            run_start: 0,
+            pos: 0,
+        }
+    }
+
+    // Feeds in the next byte, returning the number of bytes processed so far if the last
+    // `window` bytes are all different (which is how the puzzle reports markers).
+    pub fn push(&mut self, b: u8) -> Option<usize> {
+        self.run_start = self.run_start.max(self.last_seen[b as usize]);
+        self.pos += 1;
+        self.last_seen[b as usize] = self.pos;
+        (self.pos - self.run_start >= self.window).then_some(self.pos)
+    }
+
+    // Feeds in the next chunk of a stream, yielding the positions (from the start of the
+    // stream) of the markers that end in it, including those that span earlier chunks.
+    pub fn feed<'a>(&'a mut self, chunk: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        chunk.iter().filter_map(|&b| self.push(b))
     }
 }
 
-impl LowerAsciiCharset {
-    fn count(&self) -> u32 {
-        self.0.count_ones()
+pub fn marker_positions(input: &[u8], window: usize) -> impl Iterator<Item = usize> + '_ {
+    let mut detector = MarkerDetector::new(window);
+    input.iter().filter_map(move |&b| detector.push(b))
+}
+
+const READ_BUF_LEN: usize = 1 << 16;
+
+// Yields the markers in a stream as they're read, without holding more than a buffer's worth
+// of it in memory.
+pub struct MarkerReader<R> {
+    reader: R,
+    detector: MarkerDetector,
+    buf: Box<[u8]>,
+    len: usize,
+    idx: usize,
+}
+
+impl<R: Read> MarkerReader<R> {
+    pub fn new(reader: R, window: usize) -> Self {
+        Self {
+            reader,
+            detector: MarkerDetector::new(window),
+            buf: vec![0; READ_BUF_LEN].into_boxed_slice(),
+            len: 0,
+            idx: 0,
+        }
     }
 }
 
-fn start_marker_pos<const BUF_LEN: usize>(input: &str) -> usize {
-    let mut elms: ArrayVec<u8, BUF_LEN> = input.bytes().take(BUF_LEN).collect();
-
-    let mut oldest_idx = 0;
-    input
-        .as_bytes()
-        .iter()
-        .skip(BUF_LEN)
-        .position(|&b| {
-            let count = LowerAsciiCharset::from(elms.iter().chain(iter::once(&b))).count();
-            if count > BUF_LEN as u32 {
-                true
-            } else {
-                *elms.get_mut(oldest_idx).unwrap() = b;
-                oldest_idx = (oldest_idx + 1) % elms.len();
-                false
+impl<R: Read> Iterator for MarkerReader<R> {
+    type Item = io::Result<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while self.idx < self.len {
+                let b = self.buf[self.idx];
+                self.idx += 1;
+                if let Some(pos) = self.detector.push(b) {
+                    return Some(Ok(pos));
+                }
+            }
+            match self.reader.read(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(n) => (self.len, self.idx) = (n, 0),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Some(Err(e)),
             }
-        })
+        }
+    }
+}
+
+fn start_marker_pos(input: &str, window: usize) -> usize {
+    marker_positions(input.as_bytes(), window)
+        .next()
         .expect("marker not found")
-        + BUF_LEN
-        + 1
 }
 
 pub fn part_1(input: &str) -> usize {
-    start_marker_pos::<3>(input)
+    start_marker_pos(input, 4)
 }
 
 pub fn part_2(input: &str) -> usize {
-    start_marker_pos::<13>(input)
+    start_marker_pos(input, 14)
 }
 
 #[cfg(test)]
@@ -64,4 +113,54 @@ mod tests {
         assert_eq!(part_1(input), 7);
         assert_eq!(part_2(input), 19);
     }
+
+    #[test]
+    fn test_marker_positions() {
+        assert_eq!(
+            marker_positions(b"mjqjpqmgbljsphdztnvjfqwrcgsmlb", 4)
+                .take(4)
+                .collect::<Vec<_>>(),
+            [7, 8, 9, 10]
+        );
+        assert_eq!(
+            marker_positions(b"aabbaab", 2).collect::<Vec<_>>(),
+            [3, 5, 7]
+        );
+        assert_eq!(marker_positions(b"aaaa", 2).count(), 0);
+        assert_eq!(marker_positions(b"abc", 1).count(), 3);
+
+        let bytes = [0, 255, 0, b'A', 0x80, 255, b'~'];
+        assert_eq!(marker_positions(&bytes, 4).collect::<Vec<_>>(), [5, 6, 7]);
+        let long = (0..=255).chain(0..=255).collect::<Vec<u8>>();
+        assert_eq!(marker_positions(&long, 256).count(), 257);
+    }
+
+    #[test]
+    fn test_streaming() {
+        let input = b"mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+        let expected = marker_positions(input, 4).collect::<Vec<_>>();
+
+        let mut detector = MarkerDetector::new(4);
+        let mut found = vec![];
+        for chunk in input.chunks(3) {
+            found.extend(detector.feed(chunk));
+        }
+        assert_eq!(found, expected);
+
+        // one byte per read, so every marker spans several reads
+        struct Trickle<'a>(&'a [u8]);
+        impl Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.0.take(1).read(buf).inspect(|&n| self.0 = &self.0[n..])
+            }
+        }
+        let found = MarkerReader::new(Trickle(input), 4)
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(found, expected);
+
+        let long = input.repeat(READ_BUF_LEN / input.len() + 2);
+        let found = MarkerReader::new(&long[..], 14).count();
+        assert_eq!(found, marker_positions(&long, 14).count());
+    }
 }
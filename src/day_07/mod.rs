@@ -1,17 +1,28 @@
+use std::fmt;
+
 use itertools::Itertools;
+use nom::branch::alt;
+use nom::bytes::complete::{escaped_transform, take_while_m_n};
+use nom::character::complete::{char, multispace0, none_of, u32};
+use nom::combinator::{all_consuming, map, map_opt, opt, value};
+use nom::multi::separated_list0;
+use nom::sequence::{delimited, preceded, separated_pair};
 use slab::Slab;
 
+use crate::json;
+
 pub type Disk = Slab<Node>;
 pub struct File {
+    name: String,
     size: u32,
-    // not needed in the end
-    // name: String,
 }
 pub struct Node {
     name: String,
     files: Vec<File>,
     children: Vec<usize>,
     parent: Option<usize>,
+    // the total size of everything under this directory, see `Input::update_sizes`
+    size: u32,
 }
 pub struct Input {
     disk: Disk,
@@ -25,18 +36,19 @@ impl Node {
             files: vec![],
             children: vec![],
             parent: None,
+            size: 0,
         }
     }
 
-    fn total_size(disk: &Disk, node_idx: usize) -> u32 {
-        let node = disk.get(node_idx).unwrap();
-        let dir_size = node
-            .children
-            .iter()
-            .map(|c| Node::total_size(disk, *c))
+    fn update_size(disk: &mut Disk, node_idx: usize) -> u32 {
+        let children = disk[node_idx].children.clone();
+        let dir_size = children
+            .into_iter()
+            .map(|c| Node::update_size(disk, c))
             .sum::<u32>();
-        let file_size = node.files.iter().map(|f| f.size).sum::<u32>();
-        dir_size + file_size
+        let node = &mut disk[node_idx];
+        node.size = dir_size + node.files.iter().map(|f| f.size).sum::<u32>();
+        node.size
     }
 
     fn resolve(disk: &mut Disk, node_idx: usize, name: String) -> usize {
@@ -58,13 +70,6 @@ impl Node {
         };
         out
     }
-
-    fn root(fs: &Disk, mut node_idx: usize) -> usize {
-        while let Some(p) = fs.get(node_idx).and_then(|n| n.parent) {
-            node_idx = p;
-        }
-        node_idx
-    }
 }
 
 struct DiskWalker<'a> {
@@ -94,65 +99,705 @@ impl Iterator for DiskWalker<'_> {
     }
 }
 
-pub fn input_generator(input: &str) -> Input {
-    let mut disk = Disk::with_capacity(1024);
-
-    let mut lines = input.lines();
-    let root: usize = disk.insert(Node::new("/".to_string()));
-    let mut cur_node: usize = root;
-
-    while let Some(line) = lines.next() {
-        if let Some(cmd) = line.strip_prefix('$').map(|s| s.trim_start()) {
-            match cmd {
-                x if x.starts_with("cd") => {
-                    let (_, dir) = x.split_once(' ').expect("expected dir after cd");
-                    cur_node = match dir {
-                        "/" => Node::root(&disk, cur_node),
-                        ".." => disk
-                            .get(cur_node)
-                            .unwrap()
-                            .parent
-                            .expect("parent does not exist"),
-                        x => Node::resolve(&mut disk, cur_node, x.to_string()),
-                    };
+impl Input {
+    // Caches the total size of every directory, which must be done after modifying the disk.
+    pub fn update_sizes(&mut self) {
+        Node::update_size(&mut self.disk, self.root);
+    }
+
+    pub fn size(&self, dir: usize) -> u32 {
+        self.disk[dir].size
+    }
+
+    // Every directory, including the root.
+    pub fn dirs(&self) -> impl Iterator<Item = usize> + '_ {
+        std::iter::once(self.root).chain(DiskWalker::from(self))
+    }
+
+    pub fn path(&self, dir: usize) -> String {
+        let mut names = vec![];
+        let mut cur = dir;
+        while let Some(parent) = self.disk[cur].parent {
+            names.push(self.disk[cur].name.as_str());
+            cur = parent;
+        }
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    // Resolves an absolute path like `/a/e` to a directory.
+    pub fn resolve_path(&self, path: &str) -> Option<usize> {
+        path.strip_prefix('/')?
+            .split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(self.root, |dir, name| {
+                self.disk[dir]
+                    .children
+                    .iter()
+                    .copied()
+                    .find(|&c| self.disk[c].name == name)
+            })
+    }
+
+    // Like `find -type d -size`, the directories with a total size in the range.
+    pub fn find_dirs(&self, min_size: u32, max_size: u32) -> impl Iterator<Item = usize> + '_ {
+        self.dirs()
+            .filter(move |&d| (min_size..=max_size).contains(&self.size(d)))
+    }
+
+    // Like `du -d N`, the path and size of every directory at most `depth` below the root.
+    pub fn du(&self, depth: usize) -> Vec<(String, u32)> {
+        let mut out = vec![];
+        let mut to_explore = vec![(self.root, 0)];
+        while let Some((dir, d)) = to_explore.pop() {
+            out.push((self.path(dir), self.size(dir)));
+            if d < depth {
+                to_explore.extend(self.disk[dir].children.iter().map(|&c| (c, d + 1)));
+            }
+        }
+        out.sort();
+        out
+    }
+
+    // The paths and sizes of the `n` largest files, largest first.
+    pub fn largest_files(&self, n: usize) -> Vec<(String, u32)> {
+        self.dirs()
+            .flat_map(|d| self.disk[d].files.iter().map(move |f| (d, f)))
+            .sorted_by(|(_, a), (_, b)| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)))
+            .take(n)
+            .map(|(d, f)| (join_path(&self.path(d), &f.name), f.size))
+            .collect()
+    }
+
+    // Draws the tree the same way as the puzzle description.
+    pub fn render_tree(&self) -> String {
+        let mut out = String::new();
+        self.render_dir(self.root, 0, &mut out);
+        out
+    }
+
+    fn render_dir(&self, dir: usize, depth: usize, out: &mut String) {
+        out.push_str(&format!(
+            "{}- {} (dir)\n",
+            "  ".repeat(depth),
+            self.disk[dir].name
+        ));
+        for (name, entry) in self.entries(dir) {
+            match entry {
+                Entry::Dir(child) => self.render_dir(child, depth + 1, out),
+                Entry::File(size) => out.push_str(&format!(
+                    "{}- {name} (file, size={size})\n",
+                    "  ".repeat(depth + 1)
+                )),
+            }
+        }
+    }
+
+    // The files and subdirectories of a directory, sorted by name.
+    fn entries(&self, dir: usize) -> Vec<(&str, Entry)> {
+        let node = &self.disk[dir];
+        let dirs = node
+            .children
+            .iter()
+            .map(|&c| (self.disk[c].name.as_str(), Entry::Dir(c)));
+        let files = node
+            .files
+            .iter()
+            .map(|f| (f.name.as_str(), Entry::File(f.size)));
+        dirs.chain(files).sorted_by_key(|(name, _)| *name).collect()
+    }
+
+    fn new() -> Self {
+        let mut disk = Disk::with_capacity(1024);
+        let root = disk.insert(Node::new("/".to_string()));
+        Input { disk, root }
+    }
+
+    // Follows a relative or absolute path from `from`, creating any directories that don't exist
+    // yet. `..` at the root stays at the root, like a real shell. Fails with the path of the
+    // file in the way if the path goes through one.
+    fn walk(&mut self, from: usize, path: &str) -> Result<usize, String> {
+        let start = if path.starts_with('/') {
+            self.root
+        } else {
+            from
+        };
+        path.split('/').try_fold(start, |dir, name| match name {
+            "" | "." => Ok(dir),
+            ".." => Ok(self.disk[dir].parent.unwrap_or(dir)),
+            name => self
+                .add_dir(dir, name)
+                .ok_or_else(|| join_path(&self.path(dir), name)),
+        })
+    }
+}
+
+// What adding a file did to a directory that might already have one with the same name.
+enum Added {
+    New,
+    Unchanged,
+    Resized { old: u32 },
+    // there's a directory with that name, which is kept
+    Conflict,
+}
+
+impl Input {
+    // Adds a file, or updates the size of the one already there.
+    fn add_file(&mut self, dir: usize, name: &str, size: u32) -> Added {
+        let node = &self.disk[dir];
+        if node.children.iter().any(|&c| self.disk[c].name == name) {
+            return Added::Conflict;
+        }
+        let files = &mut self.disk[dir].files;
+        match files.iter_mut().find(|f| f.name == name) {
+            Some(file) if file.size != size => {
+                let old = file.size;
+                file.size = size;
+                Added::Resized { old }
+            }
+            Some(_) => Added::Unchanged,
+            None => {
+                files.push(File {
+                    name: name.to_string(),
+                    size,
+                });
+                Added::New
+            }
+        }
+    }
+
+    // Finds or creates a subdirectory, unless there's a file with that name.
+    fn add_dir(&mut self, dir: usize, name: &str) -> Option<usize> {
+        if self.disk[dir].files.iter().any(|f| f.name == name) {
+            return None;
+        }
+        Some(Node::resolve(&mut self.disk, dir, name.to_string()))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Entry {
+    Dir(usize),
+    File(u32),
+}
+
+// Something in the transcript that doesn't add up, with the (1-based) line it was found on.
+// The parser carries on regardless: a file that changed size takes the new size, an entry listed
+// as the other kind keeps the old entry, and lines it can't understand are skipped.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Warning {
+    SizeChanged {
+        line: usize,
+        path: String,
+        old: u32,
+        new: u32,
+    },
+    KindChanged {
+        line: usize,
+        path: String,
+    },
+    Missing {
+        line: usize,
+        path: String,
+    },
+    WrongPwd {
+        line: usize,
+        expected: String,
+        found: String,
+    },
+    UnknownCommand {
+        line: usize,
+        command: String,
+    },
+    // `ls` output that isn't `dir <name>` or `<size> <name>`, like the `total` line of `ls -l`
+    BadEntry {
+        line: usize,
+        text: String,
+    },
+    // output found before any command
+    NotACommand {
+        line: usize,
+        text: String,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::SizeChanged {
+                line,
+                path,
+                old,
+                new,
+            } => {
+                write!(f, "line {line}: {path} changed size from {old} to {new}")
+            }
+            Warning::KindChanged { line, path } => {
+                write!(
+                    f,
+                    "line {line}: {path} is listed as both a file and a directory"
+                )
+            }
+            Warning::Missing { line, path } => {
+                write!(f, "line {line}: {path} is missing from the listing")
+            }
+            Warning::WrongPwd {
+                line,
+                expected,
+                found,
+            } => {
+                write!(
+                    f,
+                    "line {line}: pwd printed {found} but expected {expected}"
+                )
+            }
+            Warning::UnknownCommand { line, command } => {
+                write!(f, "line {line}: skipping unknown command `{command}`")
+            }
+            Warning::BadEntry { line, text } => {
+                write!(f, "line {line}: skipping unexpected `ls` output `{text}`")
+            }
+            Warning::NotACommand { line, text } => {
+                write!(f, "line {line}: skipping `{text}`, which isn't a command")
+            }
+        }
+    }
+}
+
+fn join_path(dir: &str, name: &str) -> String {
+    let sep = if dir.ends_with('/') { "" } else { "/" };
+    format!("{dir}{sep}{name}")
+}
+
+struct Shell {
+    input: Input,
+    cwd: usize,
+    warnings: Vec<Warning>,
+}
+
+impl Shell {
+    // Merges a listing of a directory into what's already known about it, so listing the same
+    // directory twice doesn't count its files twice.
+    fn ls(&mut self, dir: usize, start_line: usize, output: &[&str]) {
+        let dir_path = self.input.path(dir);
+        let mut listed = vec![];
+        for (line, entry) in (start_line..).zip(output) {
+            // the size is `None` for a directory
+            let parsed = entry.split_once(' ').and_then(|(kind, name)| match kind {
+                "dir" => Some((None, name)),
+                size => Some((Some(size.parse::<u32>().ok()?), name)),
+            });
+            let Some((size, name)) = parsed.filter(|(_, name)| !name.is_empty()) else {
+                self.warnings.push(Warning::BadEntry {
+                    line,
+                    text: entry.to_string(),
+                });
+                continue;
+            };
+            let path = join_path(&dir_path, name);
+            match size {
+                None => {
+                    if self.input.add_dir(dir, name).is_none() {
+                        self.warnings.push(Warning::KindChanged { line, path });
+                    }
                 }
-                "ls" => lines
-                    .take_while_ref(|line| !line.starts_with('$'))
-                    .for_each(|line| match line.split_once(' ') {
-                        Some(("dir", dir)) => {
-                            Node::resolve(&mut disk, cur_node, dir.to_string());
-                        }
-                        Some((size, _name)) => {
-                            let file = File {
-                                // name: name.to_string(),
-                                size: size.parse().expect("couldn't parse size"),
-                            };
-                            disk.get_mut(cur_node).unwrap().files.push(file);
-                        }
-                        None => panic!("Couldn't find space in `ls` output"),
+                Some(size) => match self.input.add_file(dir, name, size) {
+                    Added::Conflict => self.warnings.push(Warning::KindChanged { line, path }),
+                    Added::Resized { old } => self.warnings.push(Warning::SizeChanged {
+                        line,
+                        path,
+                        old,
+                        new: size,
                     }),
-                _ => panic!("Unexpected command {cmd}"),
+                    Added::New | Added::Unchanged => {}
+                },
             }
+            listed.push(name);
+        }
+
+        let node = &self.input.disk[dir];
+        let known = node.files.iter().map(|f| f.name.as_str()).chain(
+            node.children
+                .iter()
+                .map(|&c| self.input.disk[c].name.as_str()),
+        );
+        for name in known.filter(|name| !listed.contains(name)) {
+            self.warnings.push(Warning::Missing {
+                line: start_line - 1,
+                path: join_path(&dir_path, name),
+            });
         }
     }
-    Input { disk, root }
+
+    // Walks from the current directory, warning (and returning `None`) if there's a file in the way.
+    fn walk(&mut self, line: usize, path: &str) -> Option<usize> {
+        match self.input.walk(self.cwd, path) {
+            Ok(dir) => Some(dir),
+            Err(path) => {
+                self.warnings.push(Warning::KindChanged { line, path });
+                None
+            }
+        }
+    }
+
+    fn run(&mut self, line: usize, command: &str, output: &[&str]) {
+        // `cd` takes the rest of the line so that names can contain spaces
+        let (name, args) = command.split_once(' ').unwrap_or((command, ""));
+        match name {
+            "cd" if args.is_empty() => self.cwd = self.input.root,
+            // `cd` into a file stays put
+            "cd" => self.cwd = self.walk(line, args).unwrap_or(self.cwd),
+            "ls" => {
+                // like `cd`, the path is the rest of the line after any flags
+                let mut path = args.trim_start();
+                while path.starts_with('-') {
+                    path = path
+                        .split_once(' ')
+                        .map_or("", |(_, rest)| rest.trim_start());
+                }
+                if let Some(dir) = self.walk(line, path) {
+                    self.ls(dir, line + 1, output);
+                }
+            }
+            "mkdir" => {
+                for path in args.split_whitespace().filter(|a| !a.starts_with('-')) {
+                    self.walk(line, path);
+                }
+            }
+            "pwd" => {
+                let expected = self.input.path(self.cwd);
+                if let Some(found) = output.first().filter(|&&found| found != expected) {
+                    self.warnings.push(Warning::WrongPwd {
+                        line: line + 1,
+                        expected,
+                        found: found.to_string(),
+                    });
+                }
+            }
+            _ => self.warnings.push(Warning::UnknownCommand {
+                line,
+                command: command.to_string(),
+            }),
+        }
+    }
+}
+
+// Replays a shell transcript. Besides `cd` and `ls` (of any directory) it understands `pwd` and
+// `mkdir`, and any other command (and its output) is skipped with a warning.
+pub fn parse_transcript(input: &str) -> (Input, Vec<Warning>) {
+    let disk = Input::new();
+    let mut shell = Shell {
+        cwd: disk.root,
+        input: disk,
+        warnings: vec![],
+    };
+
+    let mut lines = (1..).zip(input.lines()).peekable();
+    while let Some((line, text)) = lines.next() {
+        let Some(command) = text.strip_prefix('$') else {
+            shell.warnings.push(Warning::NotACommand {
+                line,
+                text: text.to_string(),
+            });
+            continue;
+        };
+        let output = lines
+            .peeking_take_while(|(_, text)| !text.starts_with('$'))
+            .map(|(_, text)| text)
+            .collect_vec();
+        shell.run(line, command.trim(), &output);
+    }
+
+    let mut input = shell.input;
+    input.update_sizes();
+    (input, shell.warnings)
+}
+
+pub fn input_generator(input: &str) -> Input {
+    parse_transcript(input).0
+}
+
+// Why a JSON document or a listing couldn't be turned back into a disk.
+#[derive(Debug, PartialEq, Eq)]
+pub enum FormatError {
+    // the text isn't valid JSON (or uses something unsupported), at this byte offset
+    Syntax { offset: usize },
+    // the JSON is valid but doesn't describe a directory
+    Schema(String),
+    // a line of the `find` listing couldn't be understood
+    Listing { line: usize },
+}
+
+fn valid_name(name: &str) -> bool {
+    !matches!(name, "" | "." | "..") && !name.contains(['/', '\n'])
+}
+
+enum Json {
+    Str(String),
+    Num(u32),
+    Arr(Vec<Json>),
+    Obj(Vec<(String, Json)>),
+}
+
+type IResult<'a, T> = nom::IResult<&'a str, T>;
+
+fn json_string(input: &str) -> IResult<'_, String> {
+    let hex = take_while_m_n(4, 4, |c: char| c.is_ascii_hexdigit());
+    let unicode = map_opt(preceded(char('u'), hex), |h: &str| {
+        u32::from_str_radix(h, 16).ok().and_then(char::from_u32)
+    });
+    let escape = alt((
+        value('"', char('"')),
+        value('\\', char('\\')),
+        value('/', char('/')),
+        value('\n', char('n')),
+        value('\r', char('r')),
+        value('\t', char('t')),
+        unicode,
+    ));
+    delimited(
+        char('"'),
+        map(
+            opt(escaped_transform(none_of("\\\""), '\\', escape)),
+            Option::unwrap_or_default,
+        ),
+        char('"'),
+    )(input)
+}
+
+// Just enough JSON for the disk format: strings, unsigned integers, arrays and objects.
+fn json_value(input: &str) -> IResult<'_, Json> {
+    let array = delimited(
+        char('['),
+        separated_list0(char(','), json_value),
+        preceded(multispace0, char(']')),
+    );
+    let member = separated_pair(
+        delimited(multispace0, json_string, multispace0),
+        char(':'),
+        json_value,
+    );
+    let object = delimited(
+        char('{'),
+        separated_list0(char(','), member),
+        preceded(multispace0, char('}')),
+    );
+    delimited(
+        multispace0,
+        alt((
+            map(json_string, Json::Str),
+            map(u32, Json::Num),
+            map(array, Json::Arr),
+            map(object, Json::Obj),
+        )),
+        multispace0,
+    )(input)
+}
+
+impl Json {
+    fn get(&self, key: &str) -> Result<&Json, FormatError> {
+        let Json::Obj(members) = self else {
+            return Err(FormatError::Schema("expected an object".to_string()));
+        };
+        members
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+            .ok_or_else(|| FormatError::Schema(format!("missing `{key}`")))
+    }
+
+    fn name(&self) -> Result<&str, FormatError> {
+        match self.get("name")? {
+            Json::Str(name) => Ok(name),
+            _ => Err(FormatError::Schema("`name` should be a string".to_string())),
+        }
+    }
+
+    fn array(&self, key: &str) -> Result<&[Json], FormatError> {
+        match self.get(key)? {
+            Json::Arr(items) => Ok(items),
+            _ => Err(FormatError::Schema(format!("`{key}` should be an array"))),
+        }
+    }
+}
+
+impl Input {
+    // A directory is `{"name": ..., "files": [{"name": ..., "size": ...}], "dirs": [...]}`,
+    // with the entries sorted by name.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(self.root, &mut out);
+        out
+    }
+
+    fn write_json(&self, dir: usize, out: &mut String) {
+        out.push_str("{\"name\":");
+        json::write_string(&self.disk[dir].name, out);
+        let (dirs, files): (Vec<_>, Vec<_>) = self
+            .entries(dir)
+            .into_iter()
+            .partition(|(_, entry)| matches!(entry, Entry::Dir(_)));
+        out.push_str(",\"files\":[");
+        for (i, (name, entry)) in files.into_iter().enumerate() {
+            let Entry::File(size) = entry else {
+                unreachable!()
+            };
+            out.push_str(if i == 0 { "{\"name\":" } else { ",{\"name\":" });
+            json::write_string(name, out);
+            out.push_str(&format!(",\"size\":{size}}}"));
+        }
+        out.push_str("],\"dirs\":[");
+        for (i, (_, entry)) in dirs.into_iter().enumerate() {
+            let Entry::Dir(child) = entry else {
+                unreachable!()
+            };
+            if i > 0 {
+                out.push(',');
+            }
+            self.write_json(child, out);
+        }
+        out.push_str("]}");
+    }
+
+    pub fn from_json(json: &str) -> Result<Input, FormatError> {
+        let root = match all_consuming(json_value)(json) {
+            Ok((_, root)) => root,
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+                return Err(FormatError::Syntax {
+                    offset: json.len() - e.input.len(),
+                })
+            }
+            Err(nom::Err::Incomplete(_)) => unreachable!("only complete parsers are used"),
+        };
+        let mut input = Input::new();
+        input.read_json(input.root, &root)?;
+        input.update_sizes();
+        Ok(input)
+    }
+
+    fn read_json(&mut self, dir: usize, json: &Json) -> Result<(), FormatError> {
+        for file in json.array("files")? {
+            let (name, size) = match (file.name()?, file.get("size")?) {
+                (name, &Json::Num(size)) if valid_name(name) => (name, size),
+                (name, _) => return Err(FormatError::Schema(format!("invalid file `{name}`"))),
+            };
+            if let Added::Conflict = self.add_file(dir, name, size) {
+                return Err(FormatError::Schema(format!("duplicate name `{name}`")));
+            }
+        }
+        for child in json.array("dirs")? {
+            let name = child.name()?;
+            if !valid_name(name) {
+                return Err(FormatError::Schema(format!("invalid directory `{name}`")));
+            }
+            let child_idx = self
+                .add_dir(dir, name)
+                .ok_or_else(|| FormatError::Schema(format!("duplicate name `{name}`")))?;
+            self.read_json(child_idx, child)?;
+        }
+        Ok(())
+    }
+
+    // Like `find /` but with the sizes of files: one line per entry, with directories ending in
+    // a `/` and files followed by a space and their size.
+    pub fn to_listing(&self) -> String {
+        let mut out = String::new();
+        self.write_listing(self.root, &mut out);
+        out
+    }
+
+    fn write_listing(&self, dir: usize, out: &mut String) {
+        let path = self.path(dir);
+        let sep = if dir == self.root { "" } else { "/" };
+        out.push_str(&format!("{path}{sep}\n"));
+        for (name, entry) in self.entries(dir) {
+            match entry {
+                Entry::Dir(child) => self.write_listing(child, out),
+                Entry::File(size) => out.push_str(&format!("{} {size}\n", join_path(&path, name))),
+            }
+        }
+    }
+
+    pub fn from_listing(listing: &str) -> Result<Input, FormatError> {
+        let mut input = Input::new();
+        for (line, entry) in (1..).zip(listing.lines()) {
+            let error = || FormatError::Listing { line };
+            if !entry.starts_with('/') {
+                return Err(error());
+            }
+            if entry.ends_with('/') {
+                input.walk(input.root, entry).map_err(|_| error())?;
+                continue;
+            }
+            let (path, size) = entry.rsplit_once(' ').ok_or_else(error)?;
+            let (dir, name) = path.rsplit_once('/').ok_or_else(error)?;
+            let size = size.parse().map_err(|_| error())?;
+            if !valid_name(name) {
+                return Err(error());
+            }
+            let dir = input.walk(input.root, dir).map_err(|_| error())?;
+            if let Added::Conflict = input.add_file(dir, name, size) {
+                return Err(error());
+            }
+        }
+        input.update_sizes();
+        Ok(input)
+    }
+
+    // A `cd`/`ls` session that lists every directory, which `parse_transcript` turns back into
+    // the same disk.
+    pub fn to_transcript(&self) -> String {
+        let mut out = "$ cd /\n".to_string();
+        self.write_transcript(self.root, &mut out);
+        out
+    }
+
+    fn write_transcript(&self, dir: usize, out: &mut String) {
+        let entries = self.entries(dir);
+        out.push_str("$ ls\n");
+        for (name, entry) in &entries {
+            match entry {
+                Entry::Dir(_) => out.push_str(&format!("dir {name}\n")),
+                Entry::File(size) => out.push_str(&format!("{size} {name}\n")),
+            }
+        }
+        for (name, entry) in entries {
+            if let Entry::Dir(child) = entry {
+                out.push_str(&format!("$ cd {name}\n"));
+                self.write_transcript(child, out);
+                out.push_str("$ cd ..\n");
+            }
+        }
+    }
+}
+
+// Two disks are equal when they have the same tree, however their nodes are laid out.
+impl PartialEq for Input {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_listing() == other.to_listing()
+    }
+}
+
+impl fmt::Debug for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render_tree())
+    }
 }
 
 pub fn part_1(input: &Input) -> u32 {
-    DiskWalker::from(input)
-        .map(|n| Node::total_size(&input.disk, n))
-        .filter(|s| *s <= 100000)
-        .sum()
+    input.find_dirs(0, 100_000).map(|d| input.size(d)).sum()
 }
 
 pub fn part_2(input: &Input) -> u32 {
-    let total_size = Node::total_size(&input.disk, input.root);
-    let unused_space_required = 30_000_000;
-    let remaining_space = 70_000_000 - total_size;
-    let min_delete_size = unused_space_required - remaining_space;
-    DiskWalker::from(input)
-        .map(|n| Node::total_size(&input.disk, n))
-        .filter(|s| *s >= min_delete_size)
+    let free = 70_000_000u32.saturating_sub(input.size(input.root));
+    let min_delete_size = 30_000_000u32.saturating_sub(free);
+    // there's already enough space
+    if min_delete_size == 0 {
+        return 0;
+    }
+    input
+        .find_dirs(min_delete_size, u32::MAX)
+        .map(|d| input.size(d))
         .min()
         .expect("no node found that is large enough to save the space")
 }
@@ -162,21 +807,286 @@ mod tests {
     use super::*;
     use indoc::indoc;
 
+    const EXAMPLE: &str = indoc! {"
+            $ cd /
+            $ ls
+            dir a
+            14848514 b.txt
+            8504156 c.dat
+            dir d
+            $ cd a
+            $ ls
+            dir e
+            29116 f
+            2557 g
+            62596 h.lst
+            $ cd e
+            $ ls
+            584 i
+            $ cd ..
+            $ cd ..
+            $ cd d
+            $ ls
+            4060174 j
+            8033020 d.log
+            5626152 d.ext
+            7214296 k
+        "};
+
     #[test]
     fn test() {
-        let input = input_generator(indoc! {"
+        let input = input_generator(EXAMPLE);
+        assert_eq!(part_1(&input), 95437);
+        assert_eq!(part_2(&input), 24933642);
+
+        assert_eq!(
+            input.render_tree(),
+            indoc! {"
+                - / (dir)
+                  - a (dir)
+                    - e (dir)
+                      - i (file, size=584)
+                    - f (file, size=29116)
+                    - g (file, size=2557)
+                    - h.lst (file, size=62596)
+                  - b.txt (file, size=14848514)
+                  - c.dat (file, size=8504156)
+                  - d (dir)
+                    - d.ext (file, size=5626152)
+                    - d.log (file, size=8033020)
+                    - j (file, size=4060174)
+                    - k (file, size=7214296)
+            "}
+        );
+
+        let e = input.resolve_path("/a/e").unwrap();
+        assert_eq!(input.path(e), "/a/e");
+        assert_eq!(input.size(e), 584);
+        assert_eq!(input.resolve_path("/"), Some(input.root));
+        assert_eq!(input.resolve_path("/a/x"), None);
+        assert_eq!(input.resolve_path("a"), None);
+
+        assert_eq!(
+            input.du(1),
+            [
+                ("/".to_string(), 48381165),
+                ("/a".to_string(), 94853),
+                ("/d".to_string(), 24933642)
+            ]
+        );
+        assert_eq!(
+            input.largest_files(2),
+            [
+                ("/b.txt".to_string(), 14848514),
+                ("/c.dat".to_string(), 8504156)
+            ]
+        );
+        assert_eq!(
+            input.find_dirs(90_000, 100_000).collect_vec(),
+            [input.resolve_path("/a").unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_transcript() {
+        let (input, warnings) = parse_transcript(&EXAMPLE.repeat(2));
+        assert!(warnings.is_empty());
+        assert_eq!(part_1(&input), 95437);
+        assert_eq!(part_2(&input), 24933642);
+
+        let (input, warnings) = parse_transcript(indoc! {"
+            $ cd /a/b
+            $ pwd
+            /a/b
+            $ cd ../..
+            $ pwd
+            /c
+            $ ls
+            dir a
+            100 x
+            $ ls
+            dir a
+            200 x
+            $ mkdir -p d/e /f
+            $ cd ..
+            $ ls
+            100 a
+            200 x
+            dir d
+            $ rm x
+            removed 'x'
+            $ cd /d/e
+            $ ls
+            5 y
+        "});
+        assert_eq!(
+            warnings,
+            [
+                Warning::WrongPwd {
+                    line: 6,
+                    expected: "/".to_string(),
+                    found: "/c".to_string()
+                },
+                Warning::SizeChanged {
+                    line: 12,
+                    path: "/x".to_string(),
+                    old: 100,
+                    new: 200
+                },
+                Warning::KindChanged {
+                    line: 16,
+                    path: "/a".to_string()
+                },
+                Warning::Missing {
+                    line: 15,
+                    path: "/f".to_string()
+                },
+                Warning::UnknownCommand {
+                    line: 19,
+                    command: "rm x".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            warnings[4].to_string(),
+            "line 19: skipping unknown command `rm x`"
+        );
+        assert_eq!(
+            input.render_tree(),
+            indoc! {"
+                - / (dir)
+                  - a (dir)
+                    - b (dir)
+                  - d (dir)
+                    - e (dir)
+                      - y (file, size=5)
+                  - f (dir)
+                  - x (file, size=200)
+            "}
+        );
+        assert_eq!(input.size(input.root), 205);
+
+        // listings of other directories, output it can't understand and lines before any command
+        let (input, warnings) = parse_transcript(indoc! {"
+            welcome
+            $ cd /
+            $ ls a
+            dir y
+            $ ls -l -a /a/y
+            total 1
+            3 z
+            $ ls
+            dir a
+            nospace
+            x 4
+        "});
+        assert_eq!(
+            warnings,
+            [
+                Warning::NotACommand {
+                    line: 1,
+                    text: "welcome".to_string()
+                },
+                Warning::BadEntry {
+                    line: 6,
+                    text: "total 1".to_string()
+                },
+                Warning::BadEntry {
+                    line: 10,
+                    text: "nospace".to_string()
+                },
+                Warning::BadEntry {
+                    line: 11,
+                    text: "x 4".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            warnings[1].to_string(),
+            "line 6: skipping unexpected `ls` output `total 1`"
+        );
+        assert_eq!(
+            input.render_tree(),
+            indoc! {"
+                - / (dir)
+                  - a (dir)
+                    - y (dir)
+                      - z (file, size=3)
+            "}
+        );
+
+        // a listed file can't be entered or made into a directory
+        let (input, warnings) = parse_transcript(indoc! {"
+            $ ls
+            1 x
+            $ cd x
+            $ ls
+            2 y
+            $ mkdir x/z
+            $ ls /x
+            3 w
+        "});
+        assert_eq!(
+            warnings,
+            [
+                Warning::KindChanged {
+                    line: 3,
+                    path: "/x".to_string()
+                },
+                Warning::Missing {
+                    line: 4,
+                    path: "/x".to_string()
+                },
+                Warning::KindChanged {
+                    line: 6,
+                    path: "/x".to_string()
+                },
+                Warning::KindChanged {
+                    line: 7,
+                    path: "/x".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            input.render_tree(),
+            indoc! {"
+                - / (dir)
+                  - x (file, size=1)
+                  - y (file, size=2)
+            "}
+        );
+    }
+
+    #[test]
+    fn test_formats() {
+        let input = input_generator(EXAMPLE);
+        assert_eq!(Input::from_json(&input.to_json()).unwrap(), input);
+        assert_eq!(Input::from_listing(&input.to_listing()).unwrap(), input);
+        let (parsed, warnings) = parse_transcript(&input.to_transcript());
+        assert_eq!(parsed, input);
+        assert!(warnings.is_empty());
+        assert_ne!(Input::from_listing("/\n/a/\n").unwrap(), input);
+
+        let small = Input::from_listing("/a/e/i 584\n/b.txt 14848514\n/d/\n").unwrap();
+        assert_eq!(
+            small.to_listing(),
+            "/\n/a/\n/a/e/\n/a/e/i 584\n/b.txt 14848514\n/d/\n"
+        );
+        assert_eq!(
+            small.to_json(),
+            r#"{"name":"/","files":[{"name":"b.txt","size":14848514}],"dirs":[{"name":"a","files":[],"dirs":[{"name":"e","files":[{"name":"i","size":584}],"dirs":[]}]},{"name":"d","files":[],"dirs":[]}]}"#
+        );
+        assert_eq!(
+            small.to_transcript(),
+            indoc! {"
                 $ cd /
                 $ ls
                 dir a
                 14848514 b.txt
-                8504156 c.dat
                 dir d
                 $ cd a
                 $ ls
                 dir e
-                29116 f
-                2557 g
-                62596 h.lst
                 $ cd e
                 $ ls
                 584 i
@@ -184,12 +1094,101 @@ mod tests {
                 $ cd ..
                 $ cd d
                 $ ls
-                4060174 j
-                8033020 d.log
-                5626152 d.ext
-                7214296 k
-            "});
-        assert_eq!(part_1(&input), 95437);
-        assert_eq!(part_2(&input), 24933642);
+                $ cd ..
+            "}
+        );
+
+        let pretty = Input::from_json(indoc! {r#"
+            {
+                "dirs": [ { "name": "my \"dir\"", "files": [], "dirs": [] } ],
+                "files": [ { "size": 7, "name": "café menu.txt" } ],
+                "name": "/"
+            }
+        "#})
+        .unwrap();
+        assert_eq!(pretty.to_listing(), "/\n/café menu.txt 7\n/my \"dir\"/\n");
+        assert_eq!(Input::from_json(&pretty.to_json()).unwrap(), pretty);
+        assert_eq!(parse_transcript(&pretty.to_transcript()).0, pretty);
+
+        assert_eq!(
+            Input::from_json("{"),
+            Err(FormatError::Syntax { offset: 1 })
+        );
+        assert_eq!(
+            Input::from_json(r#"{"name":"/"}"#),
+            Err(FormatError::Schema("missing `files`".to_string()))
+        );
+        assert_eq!(
+            Input::from_json(
+                r#"{"name":"/","files":[],"dirs":[{"name":"a/b","files":[],"dirs":[]}]}"#
+            ),
+            Err(FormatError::Schema("invalid directory `a/b`".to_string()))
+        );
+        assert_eq!(
+            Input::from_listing("/\nb 1\n"),
+            Err(FormatError::Listing { line: 2 })
+        );
+        assert_eq!(
+            Input::from_listing("/a x\n"),
+            Err(FormatError::Listing { line: 1 })
+        );
+
+        // duplicates are merged like listing a directory twice, but a name can't be both kinds
+        let merged = Input::from_listing("/x 1\n/x 2\n/a/\n/a/\n").unwrap();
+        assert_eq!(merged.to_listing(), "/\n/a/\n/x 2\n");
+        assert_eq!(merged.size(merged.root), 2);
+        assert_eq!(
+            Input::from_listing("/a/\n/a 1\n"),
+            Err(FormatError::Listing { line: 2 })
+        );
+        let merged = Input::from_json(
+            r#"{"name":"/","files":[{"name":"x","size":1},{"name":"x","size":1}],"dirs":[]}"#,
+        )
+        .unwrap();
+        assert_eq!(merged.size(merged.root), 1);
+        assert_eq!(merged.to_listing(), "/\n/x 1\n");
+        assert_eq!(
+            Input::from_json(
+                r#"{"name":"/","files":[{"name":"x","size":1}],"dirs":[{"name":"x","files":[],"dirs":[]}]}"#
+            ),
+            Err(FormatError::Schema("duplicate name `x`".to_string()))
+        );
+
+        assert_eq!(
+            Input::from_listing("/x 1\n/x/y 2\n"),
+            Err(FormatError::Listing { line: 2 })
+        );
+
+        // every disk the transcript parser builds survives each format, however messy it was
+        let (messy, warnings) = parse_transcript(indoc! {"
+            banner
+            $ ls
+            1 x
+            dir d
+            total 5
+            $ cd x
+            $ cd d
+            $ ls
+            2 x
+            2 x
+            dir x
+            $ ls
+            3 x
+            $ mkdir /x/y e/f
+            $ cd ../..
+            $ ls d/e
+            4 g
+        "});
+        assert!(!warnings.is_empty());
+        assert_eq!(Input::from_json(&messy.to_json()).unwrap(), messy);
+        assert_eq!(Input::from_listing(&messy.to_listing()).unwrap(), messy);
+        assert_eq!(parse_transcript(&messy.to_transcript()), (messy, vec![]));
+
+        // nothing needs deleting when there's already enough space
+        assert_eq!(part_2(&Input::from_listing("/a 1\n").unwrap()), 0);
+
+        // needs 30000000 - (70000000 - 46000000) freed, which deleting /a does exactly
+        let disk = Input::from_listing("/x 40000000\n/a/y 5000000\n/a/b/z 1000000\n").unwrap();
+        assert_eq!(part_2(&disk), 6000000);
     }
 }
@@ -1,124 +1,231 @@
+use std::fmt;
 use std::iter::repeat;
 
 use itertools::Itertools;
 
+pub type Height = u32;
+
 #[derive(Clone)]
 pub struct Input {
-    data: Vec<u8>,
+    data: Vec<Height>,
     height: usize,
     width: usize,
 }
 
 impl Input {
-    fn get(&self, i: usize, j: usize) -> u8 {
+    // A grid of heights in row-major order.
+    pub fn new(width: usize, data: Vec<Height>) -> Self {
+        assert!(
+            width > 0 && data.len().is_multiple_of(width),
+            "expected every row to be {width} trees wide"
+        );
+        Input {
+            height: data.len() / width,
+            data,
+            width,
+        }
+    }
+
+    fn get(&self, i: usize, j: usize) -> Height {
         self.data[j * self.width + i]
     }
 }
 
+fn parse_row(line: &str, separated: bool) -> Vec<Height> {
+    if separated {
+        line.split([' ', ','])
+            .filter(|h| !h.is_empty())
+            .map(|h| h.parse().expect("couldn't parse height"))
+            .collect()
+    } else {
+        line.chars()
+            .map(|c| c.to_digit(10).expect("expected a digit"))
+            .collect()
+    }
+}
+
+// Rows are either a digit per tree, like the puzzle, or heights separated by spaces or commas.
+// A separator anywhere means every row is separated, so a grid one tree wide only needs one.
 pub fn input_generator(input: &str) -> Input {
-    let mut height = 0;
-    let width = input.lines().next().map_or(0, str::len);
-    let mut data = vec![];
-    for line in input.lines() {
-        data.extend(line.bytes());
-        height += 1;
+    let separated = input.contains([' ', ',']);
+    let rows = input
+        .lines()
+        .map(|line| parse_row(line, separated))
+        .collect_vec();
+    let width = rows.first().map_or(0, Vec::len);
+    if width == 0 {
+        return Input {
+            data: vec![],
+            height: 0,
+            width: 0,
+        };
     }
-    Input {
-        data,
-        height,
-        width,
+    for (j, row) in rows.iter().enumerate() {
+        assert_eq!(
+            row.len(),
+            width,
+            "expected row {} to be {width} trees wide",
+            j + 1
+        );
     }
+    Input::new(width, rows.concat())
 }
 
-fn visit_row_peaks<R, F>(input: &Input, mut visitor: F, range: R)
-where
-    R: Iterator<Item = (usize, usize)>,
-    F: FnMut(usize, usize),
-{
-    let mut largest = 0;
-    for (i, j) in range {
-        let new = input.get(i, j);
-        if new > largest {
-            visitor(i, j);
-            largest = new;
+// The side of a tree that is being looked at, from the tree or from outside the grid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+}
+
+// What a tree sees looking in one direction.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct View {
+    // every tree that way is shorter, so it can be seen from outside the grid
+    pub visible: bool,
+    // the number of trees it can see, up to and including the first one at least as tall
+    pub distance: usize,
+}
+
+// The view in every direction from every tree.
+pub struct Survey {
+    width: usize,
+    views: [Vec<View>; 4],
+}
+
+impl Survey {
+    pub fn view(&self, x: usize, y: usize, dir: Direction) -> View {
+        self.views[dir as usize][y * self.width + x]
+    }
+
+    pub fn is_visible(&self, x: usize, y: usize) -> bool {
+        Direction::ALL.iter().any(|&d| self.view(x, y, d).visible)
+    }
+
+    pub fn visible_from(&self, x: usize, y: usize) -> Vec<Direction> {
+        Direction::ALL
+            .into_iter()
+            .filter(|&d| self.view(x, y, d).visible)
+            .collect()
+    }
+
+    // The viewing distance in each direction, in the order of `Direction::ALL`.
+    pub fn scenic_scores(&self, x: usize, y: usize) -> [usize; 4] {
+        Direction::ALL.map(|d| self.view(x, y, d).distance)
+    }
+
+    // The product of the viewing distances, which can be far larger than the grid.
+    pub fn scenic_score(&self, x: usize, y: usize) -> u128 {
+        self.scenic_scores(x, y)
+            .into_iter()
+            .map(|d| d as u128)
+            .product()
+    }
+
+    pub fn visibility_map(&self) -> VisibilityMap {
+        let len = self.views[0].len();
+        VisibilityMap {
+            width: self.width,
+            visible: (0..len)
+                .map(|k| self.is_visible(k % self.width, k / self.width))
+                .collect(),
         }
     }
 }
 
-pub fn part_1(input: &Input) -> usize {
-    let mut visible_map = vec![false; input.data.len()];
-    let mut visit = |i, j| visible_map[j * input.width + i] = true;
-    for j in 0..input.height {
-        visit_row_peaks(input, &mut visit, (0..input.width).zip(repeat(j)));
-        visit_row_peaks(input, &mut visit, (0..input.width).rev().zip(repeat(j)));
+// Which trees can be seen from outside the grid, rendered with a `#` for each visible tree and
+// a `.` for each hidden one.
+pub struct VisibilityMap {
+    width: usize,
+    visible: Vec<bool>,
+}
+
+impl VisibilityMap {
+    pub fn is_visible(&self, x: usize, y: usize) -> bool {
+        self.visible[y * self.width + x]
     }
 
-    for i in 0..input.width {
-        visit_row_peaks(input, &mut visit, repeat(i).zip(0..input.height));
-        visit_row_peaks(input, &mut visit, repeat(i).zip((0..input.height).rev()));
+    pub fn count(&self) -> usize {
+        self.visible.iter().filter(|&&v| v).count()
     }
+}
 
-    visible_map.into_iter().filter(|b| *b).count()
+impl fmt::Display for VisibilityMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.visible.chunks(self.width.max(1)) {
+            let line: String = row.iter().map(|&v| if v { '#' } else { '.' }).collect();
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
 }
 
-fn visit_viewing_dist<R, F>(input: &Input, mut visitor: F, range: R)
+// Walks a line of trees away from the side being looked at, keeping a stack of the trees that
+// haven't been hidden yet by a taller one. Those are in non-increasing order of height, so a tree
+// can see back to the first one left on the stack after popping the shorter ones.
+fn survey_line<R>(input: &Input, range: R, views: &mut [View])
 where
     R: Iterator<Item = (usize, usize)>,
-    F: FnMut((usize, usize), u8),
 {
-    let mut dists = [0u8; 10];
-    for (i, j) in range {
-        let new = input.get(i, j) as usize;
-        let dist = dists[new];
-        visitor((i, j), dist);
-        dists.iter_mut().take(new + 1).for_each(|d| *d = 1);
-        dists.iter_mut().skip(new + 1).for_each(|d| *d += 1);
+    let mut blockers: Vec<(usize, Height)> = vec![];
+    for (k, (i, j)) in range.enumerate() {
+        let new = input.get(i, j);
+        while blockers.last().is_some_and(|&(_, h)| h < new) {
+            blockers.pop();
+        }
+        views[j * input.width + i] = match blockers.last() {
+            Some(&(b, _)) => View {
+                visible: false,
+                distance: k - b,
+            },
+            None => View {
+                visible: true,
+                distance: k,
+            },
+        };
+        blockers.push((k, new));
     }
 }
 
-pub fn part_2(input: &Input) -> u32 {
-    let input = &mut input.clone();
-    input.data.iter_mut().for_each(|b| *b -= b'0');
-
-    let mut dist_up_map = vec![0; input.data.len()];
-    let mut dist_left_map = vec![0; input.data.len()];
-    let mut dist_right_map = vec![0; input.data.len()];
-    let mut dist_down_map = vec![0; input.data.len()];
-
-    let put = |m: &mut Vec<u8>, (i, j), d| m[j * input.width + i] = d;
-
-    for j in 0..input.height {
-        visit_viewing_dist(
-            input,
-            |c, d| put(&mut dist_left_map, c, d),
-            (0..input.width).zip(repeat(j)),
-        );
-        visit_viewing_dist(
-            input,
-            |c, d| put(&mut dist_right_map, c, d),
-            (0..input.width).rev().zip(repeat(j)),
-        );
+impl Input {
+    pub fn survey(&self) -> Survey {
+        let mut views = Direction::ALL.map(|_| vec![View::default(); self.data.len()]);
+        let [up, down, left, right] = &mut views;
+        for j in 0..self.height {
+            survey_line(self, (0..self.width).zip(repeat(j)), left);
+            survey_line(self, (0..self.width).rev().zip(repeat(j)), right);
+        }
+        for i in 0..self.width {
+            survey_line(self, repeat(i).zip(0..self.height), up);
+            survey_line(self, repeat(i).zip((0..self.height).rev()), down);
+        }
+        Survey {
+            width: self.width,
+            views,
+        }
     }
+}
 
-    for i in 0..input.width {
-        visit_viewing_dist(
-            input,
-            |c, d| put(&mut dist_up_map, c, d),
-            repeat(i).zip(0..input.height),
-        );
-        visit_viewing_dist(
-            input,
-            |c, d| put(&mut dist_down_map, c, d),
-            repeat(i).zip((0..input.height).rev()),
-        );
-    }
+pub fn part_1(input: &Input) -> usize {
+    input.survey().visibility_map().count()
+}
 
-    dist_up_map
-        .into_iter()
-        .zip_eq(dist_down_map)
-        .zip_eq(dist_left_map)
-        .zip_eq(dist_right_map)
-        .map(|(((u, d), l), r)| u as u32 * d as u32 * l as u32 * r as u32)
+pub fn part_2(input: &Input) -> u128 {
+    let survey = input.survey();
+    (0..input.height)
+        .cartesian_product(0..input.width)
+        .map(|(y, x)| survey.scenic_score(x, y))
         .max()
         .unwrap_or(0)
 }
@@ -138,7 +245,85 @@ mod tests {
             33549
             35390
             "});
-        // assert_eq!(part_1(&input), 21);
+        assert_eq!(part_1(&input), 21);
         assert_eq!(part_2(&input), 8);
+
+        let survey = input.survey();
+        assert_eq!(
+            survey.visibility_map().to_string(),
+            "#####\n###.#\n##.##\n#.#.#\n#####\n"
+        );
+        // the top-left 5
+        assert!(survey.is_visible(1, 1));
+        assert_eq!(survey.visible_from(1, 1), [Direction::Up, Direction::Left]);
+        assert_eq!(survey.scenic_scores(2, 1), [1, 2, 1, 2]);
+        assert_eq!(survey.scenic_score(2, 1), 4);
+        // the 5 in the middle of the fourth row
+        assert_eq!(survey.scenic_scores(2, 3), [2, 1, 2, 2]);
+        assert_eq!(survey.scenic_score(2, 3), 8);
+        // the center 3 is hidden from every side
+        assert!(!survey.is_visible(2, 2));
+        assert!(survey.visible_from(2, 2).is_empty());
+        assert_eq!(
+            survey.view(0, 0, Direction::Left),
+            View {
+                visible: true,
+                distance: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_heights() {
+        let input = input_generator("10 2 30\n4,400,5\n0 0 0");
+        // the first row has no separator, but the grid does
+        assert_eq!(input_generator("10\n5 ").data, [10, 5]);
+        assert_eq!(part_1(&input_generator("10\n5,")), 2);
+        assert_eq!(part_1(&input), 9);
+        let survey = input.survey();
+        assert_eq!(survey.visible_from(1, 1), Direction::ALL);
+        assert_eq!(survey.scenic_score(1, 1), 1);
+
+        // viewing distances that don't fit in a byte
+        let width = 300;
+        let row = (0..width as Height).collect_vec();
+        let input = Input::new(width, [vec![0; width], row, vec![0; width]].concat());
+        assert_eq!(input.survey().scenic_scores(298, 1), [1, 1, 298, 1]);
+        assert_eq!(part_2(&input), 298);
+    }
+
+    #[test]
+    fn test_edges() {
+        assert_eq!(part_1(&input_generator("")), 0);
+        assert_eq!(part_2(&input_generator("")), 0);
+        assert_eq!(part_1(&input_generator("0")), 1);
+        assert_eq!(part_1(&input_generator("000")), 3);
+        assert_eq!(part_1(&input_generator("0\n0\n0")), 3);
+        assert_eq!(part_2(&input_generator("0\n0\n0")), 0);
+
+        // short trees on the border are still visible, and equal heights block the view
+        let input = input_generator("000\n000\n000");
+        assert_eq!(
+            input.survey().visibility_map().to_string(),
+            "###\n#.#\n###\n"
+        );
+        assert_eq!(part_1(&input), 8);
+        let input = input_generator("000\n010\n000");
+        assert_eq!(part_1(&input), 9);
+        assert!(input.survey().visibility_map().is_visible(1, 1));
+
+        // hidden behind a taller tree on one side and trees as tall on the others
+        let input = input_generator("0000\n0900\n0880\n0000");
+        assert_eq!(
+            input.survey().visibility_map().to_string(),
+            "####\n##.#\n####\n####\n"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "expected row 2 to be 2 trees wide")]
+    fn test_ragged() {
+        // the rows would add up to a whole number of rows without the check
+        input_generator("12\n3\n456");
     }
 }
@@ -1,15 +1,21 @@
 use rustc_hash::FxHashSet;
 use std::str::Lines;
 
+pub type Pos = (i32, i32);
+
 struct Steps<'a> {
     lines: Lines<'a>,
 }
 
-enum Dir {
+pub enum Dir {
     Left,
     Right,
     Up,
     Down,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
 }
 
 impl From<&str> for Dir {
@@ -19,45 +25,56 @@ impl From<&str> for Dir {
             "R" => Dir::Right,
             "U" => Dir::Up,
             "D" => Dir::Down,
+            "UL" => Dir::UpLeft,
+            "UR" => Dir::UpRight,
+            "DL" => Dir::DownLeft,
+            "DR" => Dir::DownRight,
             _ => panic!("unexpected dir '{s}'"),
         }
     }
 }
 
-struct Step {
-    count: i32,
-    dir: Dir,
-}
-
-impl Step {
-    fn visit<F>(self, mut visitor: F, snake: &mut [(i32, i32)])
-    where
-        F: FnMut((i32, i32)),
-    {
-        let (dx, dy) = match self.dir {
+impl Dir {
+    fn delta(&self) -> Pos {
+        match self {
             Dir::Left => (-1, 0),
             Dir::Right => (1, 0),
             Dir::Down => (0, -1),
             Dir::Up => (0, 1),
-        };
+            Dir::UpLeft => (-1, 1),
+            Dir::UpRight => (1, 1),
+            Dir::DownLeft => (-1, -1),
+            Dir::DownRight => (1, -1),
+        }
+    }
+}
 
-        for _ in 0..self.count {
-            let head = snake.first_mut().unwrap();
-            head.0 += dx;
-            head.1 += dy;
-
-            // Ideally I would want to use something like Slice::windows_mut but this is apparently
-            // not possible with standard iterators.
-            for i in 1..snake.len() {
-                let head = snake[i - 1];
-                let tail = &mut snake[i];
-                let diff = (head.0 - tail.0, head.1 - tail.1);
-                if diff.0.abs() > 1 || diff.1.abs() > 1 {
-                    tail.0 += diff.0.signum();
-                    tail.1 += diff.1.signum();
-                }
+pub enum Step {
+    // `count` steps in a direction, written like `UL 3`
+    Move { dir: Dir, count: i32 },
+    // steps to a position, diagonally until it's in line and then straight, written like `TO 3,-4`
+    To(Pos),
+}
+
+impl Step {
+    // The runs of steps in the same direction that make up this step, starting from `head`.
+    fn runs(&self, head: Pos) -> Vec<(Pos, usize)> {
+        match self {
+            Step::Move { dir, count } => vec![(dir.delta(), (*count).max(0) as usize)],
+            Step::To(target) => {
+                let (dx, dy) = (target.0 - head.0, target.1 - head.1);
+                let diagonal = dx.abs().min(dy.abs());
+                let straight = dx.abs().max(dy.abs()) - diagonal;
+                let rest = if dx.abs() > dy.abs() {
+                    (dx.signum(), 0)
+                } else {
+                    (0, dy.signum())
+                };
+                vec![
+                    ((dx.signum(), dy.signum()), diagonal as usize),
+                    (rest, straight as usize),
+                ]
             }
-            visitor(*snake.last().unwrap())
         }
     }
 }
@@ -68,7 +85,14 @@ impl Iterator for Steps<'_> {
     fn next(&mut self) -> Option<Self::Item> {
         self.lines.next().map(|line| {
             let (dir, count) = line.split_once(' ').expect("line should have a space");
-            Step {
+            if dir == "TO" {
+                let (x, y) = count
+                    .split_once(',')
+                    .expect("expected a position like 3,-4");
+                let parse = |c: &str| c.trim().parse().expect("couldn't parse position");
+                return Step::To((parse(x), parse(y)));
+            }
+            Step::Move {
                 dir: dir.into(),
                 count: count.parse().expect("couldn't parse count"),
             }
@@ -76,23 +100,202 @@ impl Iterator for Steps<'_> {
     }
 }
 
-fn solve(input: &str, snake_len: usize) -> usize {
-    let mut visited = FxHashSet::default();
-    let mut snake = vec![(0i32, 0i32); snake_len];
-
-    let steps = Steps {
+pub fn parse_steps(input: &str) -> impl Iterator<Item = Step> + '_ {
+    Steps {
         lines: input.lines(),
-    };
-    for step in steps {
-        step.visit(
-            |tail| {
-                visited.insert(tail);
-            },
-            &mut snake,
-        );
     }
+}
+
+// Whether a knot `offset` behind the one ahead of it moves exactly as that one does, when it
+// moves by `delta`.
+fn follows_rigidly(offset: Pos, delta: Pos) -> bool {
+    let diff = (offset.0 + delta.0, offset.1 + delta.1);
+    (diff.0.abs() > 1 || diff.1.abs() > 1) && (diff.0.signum(), diff.1.signum()) == delta
+}
 
-    visited.len()
+// A rope of any number of knots starting at the origin, which remembers where each knot has been.
+pub struct Rope {
+    knots: Vec<Pos>,
+    // only the trails of some knots are kept, as they take up a lot of space on long moves
+    trails: Vec<Option<FxHashSet<Pos>>>,
+}
+
+impl Rope {
+    pub fn new(len: usize) -> Self {
+        Self::tracking(len, |_| true)
+    }
+
+    // Only keeps the trail of the last knot.
+    pub fn tail_only(len: usize) -> Self {
+        Self::tracking(len, |knot| knot == len - 1)
+    }
+
+    fn tracking<F: Fn(usize) -> bool>(len: usize, tracked: F) -> Self {
+        assert!(len > 0, "a rope needs at least a head");
+        Rope {
+            knots: vec![(0, 0); len],
+            trails: (0..len)
+                .map(|knot| tracked(knot).then(|| FxHashSet::from_iter([(0, 0)])))
+                .collect(),
+        }
+    }
+
+    pub fn knots(&self) -> &[Pos] {
+        &self.knots
+    }
+
+    // Every position the knot has been in, including the start.
+    pub fn trail(&self, knot: usize) -> &FxHashSet<Pos> {
+        self.trails[knot]
+            .as_ref()
+            .unwrap_or_else(|| panic!("the trail of knot {knot} isn't being kept"))
+    }
+
+    pub fn tail_trail(&self) -> &FxHashSet<Pos> {
+        self.trail(self.knots.len() - 1)
+    }
+
+    fn visit(&mut self, knot: usize) {
+        let pos = self.knots[knot];
+        if let Some(trail) = &mut self.trails[knot] {
+            trail.insert(pos);
+        }
+    }
+
+    // Moves the head by one step, which mustn't be more than one in each axis.
+    pub fn step(&mut self, (dx, dy): Pos) {
+        let head = &mut self.knots[0];
+        head.0 += dx;
+        head.1 += dy;
+        self.visit(0);
+
+        // Ideally I would want to use something like Slice::windows_mut but this is apparently
+        // not possible with standard iterators.
+        for i in 1..self.knots.len() {
+            let head = self.knots[i - 1];
+            let tail = &mut self.knots[i];
+            let diff = (head.0 - tail.0, head.1 - tail.1);
+            if diff.0.abs() > 1 || diff.1.abs() > 1 {
+                tail.0 += diff.0.signum();
+                tail.1 += diff.1.signum();
+                self.visit(i);
+            } else {
+                // the knots behind this one won't move either
+                break;
+            }
+        }
+    }
+
+    // Moves the head `count` times by `delta`. Once every knot follows the one ahead of it
+    // rigidly, the whole rope just slides along, so the rest of the run is skipped over.
+    pub fn run(&mut self, delta: Pos, count: usize) {
+        let mut remaining = count;
+        while remaining > 0 && !self.is_rigid(delta) {
+            self.step(delta);
+            remaining -= 1;
+        }
+        let remaining = remaining as i32;
+        for (knot, trail) in self.knots.iter_mut().zip(&mut self.trails) {
+            if let Some(trail) = trail {
+                trail.extend((1..=remaining).map(|k| (knot.0 + k * delta.0, knot.1 + k * delta.1)));
+            }
+            knot.0 += remaining * delta.0;
+            knot.1 += remaining * delta.1;
+        }
+    }
+
+    fn is_rigid(&self, delta: Pos) -> bool {
+        self.knots
+            .windows(2)
+            .all(|w| follows_rigidly((w[0].0 - w[1].0, w[0].1 - w[1].1), delta))
+    }
+
+    pub fn apply(&mut self, step: &Step) {
+        for (delta, count) in step.runs(self.knots[0]) {
+            self.run(delta, count);
+        }
+    }
+
+    // The knots after every single step of the head.
+    pub fn snapshots<'a, I>(&'a mut self, steps: I) -> impl Iterator<Item = Vec<Pos>> + 'a
+    where
+        I: IntoIterator<Item = Step> + 'a,
+    {
+        let mut steps = steps.into_iter();
+        // the runs left in the current step, in reverse order
+        let mut runs: Vec<(Pos, usize)> = vec![];
+        std::iter::from_fn(move || loop {
+            match runs.last_mut() {
+                Some((_, 0)) => {
+                    runs.pop();
+                }
+                Some((delta, count)) => {
+                    *count -= 1;
+                    self.step(*delta);
+                    return Some(self.knots.clone());
+                }
+                None => {
+                    runs = steps.next()?.runs(self.knots[0]);
+                    runs.reverse();
+                }
+            }
+        })
+    }
+
+    // The smallest bounds (inclusive) containing the start, the knots and everywhere a knot whose
+    // trail is kept has been.
+    pub fn bounds(&self) -> (Pos, Pos) {
+        let all = self.trails.iter().flatten().flatten().chain(&self.knots);
+        let (min_x, max_x) = all
+            .clone()
+            .fold((0, 0), |(lo, hi), p| (p.0.min(lo), p.0.max(hi)));
+        let (min_y, max_y) = all.fold((0, 0), |(lo, hi), p| (p.1.min(lo), p.1.max(hi)));
+        ((min_x, min_y), (max_x, max_y))
+    }
+
+    pub fn render(&self, min: Pos, max: Pos) -> String {
+        render_knots(&self.knots, min, max)
+    }
+
+    pub fn render_trail(&self, knot: usize, min: Pos, max: Pos) -> String {
+        let trail = self.trail(knot);
+        render(min, max, |p| match p {
+            (0, 0) => 's',
+            p if trail.contains(&p) => '#',
+            _ => '.',
+        })
+    }
+}
+
+// Draws the area between `min` and `max` (inclusive), with up being positive y.
+fn render<F: Fn(Pos) -> char>(min: Pos, max: Pos, tile: F) -> String {
+    let mut out = String::new();
+    for y in (min.1..=max.1).rev() {
+        out.extend((min.0..=max.0).map(|x| tile((x, y))));
+        out.push('\n');
+    }
+    out
+}
+
+// Uses the puzzle's notation: `H` for the head, then `1`, `2`... (or `T` for a rope of two), and
+// `s` for the start. When knots overlap, the one nearest the head is drawn.
+pub fn render_knots(knots: &[Pos], min: Pos, max: Pos) -> String {
+    let label = |i: usize| match i {
+        0 => 'H',
+        1 if knots.len() == 2 => 'T',
+        i => char::from_digit(i as u32 % 36, 36).unwrap(),
+    };
+    render(min, max, |p| match knots.iter().position(|&k| k == p) {
+        Some(i) => label(i),
+        None if p == (0, 0) => 's',
+        None => '.',
+    })
+}
+
+fn solve(input: &str, snake_len: usize) -> usize {
+    let mut rope = Rope::tail_only(snake_len);
+    parse_steps(input).for_each(|step| rope.apply(&step));
+    rope.tail_trail().len()
 }
 
 pub fn part_1(input: &str) -> usize {
@@ -108,22 +311,21 @@ mod tests {
     use super::*;
     use indoc::indoc;
 
+    const EXAMPLE: &str = indoc! {"
+        R 4
+        U 4
+        L 3
+        D 1
+        R 4
+        D 1
+        L 5
+        R 2
+    "};
+
     #[test]
     fn test() {
-        let input = indoc! {
-        "
-            R 4
-            U 4
-            L 3
-            D 1
-            R 4
-            D 1
-            L 5
-            R 2
-            "
-        };
-        assert_eq!(part_1(input), 13);
-        assert_eq!(part_2(input), 1);
+        assert_eq!(part_1(EXAMPLE), 13);
+        assert_eq!(part_2(EXAMPLE), 1);
 
         let input2 = indoc! {
         "
@@ -139,4 +341,80 @@ mod tests {
         };
         assert_eq!(part_2(input2), 36);
     }
+
+    #[test]
+    fn test_rope() {
+        let (min, max) = ((0, 0), (5, 4));
+        let mut rope = Rope::new(2);
+        let snapshots = rope.snapshots(parse_steps(EXAMPLE)).collect::<Vec<_>>();
+        assert_eq!(snapshots.len(), 24);
+        // the end of `R 4`, `U 4` and `L 3`
+        assert_eq!(
+            render_knots(&snapshots[3], min, max),
+            "......\n......\n......\n......\ns..TH.\n"
+        );
+        assert_eq!(
+            render_knots(&snapshots[7], min, max),
+            "....H.\n....T.\n......\n......\ns.....\n"
+        );
+        assert_eq!(
+            render_knots(&snapshots[10], min, max),
+            ".HT...\n......\n......\n......\ns.....\n"
+        );
+        assert_eq!(
+            rope.render(min, max),
+            "......\n......\n.TH...\n......\ns.....\n"
+        );
+        assert_eq!(
+            rope.render_trail(1, min, max),
+            "..##..\n...##.\n.####.\n....#.\ns###..\n"
+        );
+        assert_eq!(rope.bounds(), (min, max));
+        assert_eq!(rope.trail(0).len(), 21);
+
+        let mut rope = Rope::new(10);
+        rope.apply(&parse_steps("R 5").next().unwrap());
+        assert_eq!(rope.knots()[..5], [(5, 0), (4, 0), (3, 0), (2, 0), (1, 0)]);
+        assert_eq!(
+            rope.render((-11, 0), (14, 0)),
+            "...........54321H.........\n"
+        );
+        assert_eq!(rope.trail(4).len(), 2);
+        assert_eq!(rope.tail_trail().len(), 1);
+    }
+
+    #[test]
+    fn test_moves() {
+        let mut rope = Rope::new(2);
+        parse_steps("UR 3\nTO 3,-1").for_each(|step| rope.apply(&step));
+        assert_eq!(rope.knots(), [(3, -1), (3, 0)]);
+        assert_eq!(rope.trail(1).len(), 5);
+        assert!([(1, 1), (2, 2), (3, 1), (3, 0)]
+            .iter()
+            .all(|p| rope.trail(1).contains(p)));
+
+        // skipping ahead on long runs ends up the same as stepping through them
+        let dirs = ["L", "R", "U", "D", "UL", "UR", "DL", "DR"];
+        let mut seed = 7u32;
+        let mut random = |n: u32| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) % n
+        };
+        let input = (0..200)
+            .map(|_| match random(10) {
+                9 => format!("TO {},{}", random(100) as i32 - 50, random(100) as i32 - 50),
+                d => format!("{} {}", dirs[d as usize % 8], random(40)),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut fast = Rope::new(10);
+        parse_steps(&input).for_each(|step| fast.apply(&step));
+        let mut slow = Rope::new(10);
+        let last = slow.snapshots(parse_steps(&input)).last().unwrap();
+        assert_eq!(fast.knots(), last);
+        assert!((0..10).all(|knot| fast.trail(knot) == slow.trail(knot)));
+
+        assert_eq!(part_1("R 1000000\nU 1000000"), 1_999_999);
+        assert_eq!(part_2("DL 1000000"), 999_992);
+    }
 }
@@ -1,19 +1,165 @@
-pub enum Instruction {
-    Noop,
-    Addx(i8),
+use std::fmt;
+
+use arrayvec::ArrayVec;
+
+pub type Word = i64;
+
+// One register for each lowercase letter. `x` starts at 1 and the rest start at 0.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Registers([Word; 26]);
+
+impl Default for Registers {
+    fn default() -> Self {
+        let mut registers = Registers([0; 26]);
+        registers.0[reg_idx('x').unwrap()] = 1;
+        registers
+    }
+}
+
+fn reg_idx(name: char) -> Option<usize> {
+    name.is_ascii_lowercase()
+        .then(|| (name as u8 - b'a') as usize)
+}
+
+impl Registers {
+    pub fn get(&self, name: char) -> Word {
+        self.0[reg_idx(name).expect("registers are named a to z")]
+    }
+
+    fn value(&self, operand: Operand) -> Word {
+        match operand {
+            Operand::Reg(r) => self.0[r],
+            Operand::Imm(v) => v,
+        }
+    }
+
+    fn dest(&mut self, operand: Operand) -> &mut Word {
+        match operand {
+            Operand::Reg(r) => &mut self.0[r],
+            Operand::Imm(_) => unreachable!("destinations are checked when parsing"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operand {
+    Reg(usize),
+    Imm(Word),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    // a register that is written to
+    Dest,
+    // a register or an immediate
+    Source,
+}
+
+const MAX_OPERANDS: usize = 2;
+
+// An instruction's cycle cost and its effect on the registers, which takes place at the end of
+// its last cycle.
+pub struct Opcode {
+    pub name: &'static str,
+    pub cycles: u32,
+    pub operands: &'static [Kind],
+    pub effect: fn(&mut Registers, &[Operand]),
+}
+
+// An opcode with a destination can also be written with the register as a suffix, so `addx 3` is
+// the same as `add x 3`. Arithmetic wraps on overflow, like a real register.
+pub static OPCODES: &[Opcode] = &[
+    Opcode {
+        name: "noop",
+        cycles: 1,
+        operands: &[],
+        effect: |_, _| {},
+    },
+    Opcode {
+        name: "add",
+        cycles: 2,
+        operands: &[Kind::Dest, Kind::Source],
+        effect: |r, o| {
+            let v = r.value(o[1]);
+            let d = r.dest(o[0]);
+            *d = d.wrapping_add(v)
+        },
+    },
+    Opcode {
+        name: "sub",
+        cycles: 2,
+        operands: &[Kind::Dest, Kind::Source],
+        effect: |r, o| {
+            let v = r.value(o[1]);
+            let d = r.dest(o[0]);
+            *d = d.wrapping_sub(v)
+        },
+    },
+    Opcode {
+        name: "mul",
+        cycles: 4,
+        operands: &[Kind::Dest, Kind::Source],
+        effect: |r, o| {
+            let v = r.value(o[1]);
+            let d = r.dest(o[0]);
+            *d = d.wrapping_mul(v)
+        },
+    },
+    Opcode {
+        name: "set",
+        cycles: 1,
+        operands: &[Kind::Dest, Kind::Source],
+        effect: |r, o| *r.dest(o[0]) = r.value(o[1]),
+    },
+];
+
+pub struct Instruction {
+    opcode: &'static Opcode,
+    operands: ArrayVec<Operand, MAX_OPERANDS>,
 }
 
 pub type Input = Vec<Instruction>;
 
+fn parse_operand(s: &str) -> Operand {
+    let mut chars = s.chars();
+    match (chars.next().and_then(reg_idx), chars.next()) {
+        (Some(r), None) => Operand::Reg(r),
+        _ => Operand::Imm(s.parse().expect("couldn't parse operand as int")),
+    }
+}
+
 impl From<&str> for Instruction {
     fn from(s: &str) -> Self {
-        match s.split_once(' ') {
-            Some(("addx", x)) => {
-                Instruction::Addx(x.parse().expect("couldn't parse operand as int"))
+        let mut words = s.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let mut operands = ArrayVec::new();
+        let opcode = match OPCODES.iter().find(|op| op.name == name) {
+            Some(op) => op,
+            None => {
+                let (name, suffix) = name.split_at(name.len().saturating_sub(1));
+                let reg = suffix.chars().next().and_then(reg_idx);
+                match OPCODES.iter().find(|op| op.name == name) {
+                    Some(op) if reg.is_some() && op.operands.first() == Some(&Kind::Dest) => {
+                        operands.push(Operand::Reg(reg.unwrap()));
+                        op
+                    }
+                    _ => panic!("couldn't parse '{s}' as an instruction"),
+                }
             }
-            None if s == "noop" => Instruction::Noop,
-            _ => panic!("couldn't parse '{s}' as an instruction"),
+        };
+        for word in words {
+            operands
+                .try_push(parse_operand(word))
+                .unwrap_or_else(|_| panic!("too many operands in '{s}'"));
         }
+
+        let kinds_match = operands.len() == opcode.operands.len()
+            && operands
+                .iter()
+                .zip(opcode.operands)
+                .all(|(o, k)| *k == Kind::Source || matches!(o, Operand::Reg(_)));
+        assert!(kinds_match, "wrong operands in '{s}'");
+        Instruction { opcode, operands }
     }
 }
 
@@ -21,72 +167,140 @@ pub fn input_generator(input: &str) -> Input {
     input.lines().map(Instruction::from).collect()
 }
 
-pub struct Machine<'a, I: Iterator<Item = &'a Instruction>> {
-    reg: i32,
-    clock: i32,
-    instructions: I,
-    cur: Option<&'a Instruction>,
+// Something that is shown the registers during every cycle, numbered from 1.
+pub trait Observer {
+    fn cycle(&mut self, cycle: u64, registers: &Registers);
 }
 
-impl<'a, I: Iterator<Item = &'a Instruction>> Machine<'a, I> {
-    fn new(instructions: I) -> Self {
+impl<F: FnMut(u64, &Registers)> Observer for F {
+    fn cycle(&mut self, cycle: u64, registers: &Registers) {
+        self(cycle, registers)
+    }
+}
+
+pub struct Machine<'a> {
+    pub registers: Registers,
+    // the number of cycles that have been completed
+    clock: u64,
+    program: &'a [Instruction],
+    pc: usize,
+    // the cycles left until the current instruction takes effect
+    busy: u32,
+}
+
+impl<'a> Machine<'a> {
+    pub fn new(program: &'a [Instruction]) -> Self {
         Self {
-            reg: 1,
-            clock: 1,
-            instructions,
-            cur: None,
+            registers: Registers::default(),
+            clock: 0,
+            program,
+            pc: 0,
+            busy: 0,
         }
     }
 
-    fn tick(&mut self) {
-        if let Some(Instruction::Addx(x)) = self.cur {
-            self.reg += *x as i32;
-            self.cur = None;
-        } else if let Some(i) = self.instructions.next() {
-            match i {
-                Instruction::Addx(_) => self.cur = Some(i),
-                Instruction::Noop => {}
-            }
+    pub fn is_finished(&self) -> bool {
+        self.pc >= self.program.len()
+    }
+
+    // Runs one cycle, idling once the program has finished.
+    pub fn tick(&mut self, observers: &mut [&mut dyn Observer]) {
+        let cur = self.program.get(self.pc);
+        if self.busy == 0 {
+            self.busy = cur.map_or(1, |i| i.opcode.cycles.max(1));
         }
         self.clock += 1;
+        for observer in observers.iter_mut() {
+            observer.cycle(self.clock, &self.registers);
+        }
+        self.busy -= 1;
+        if let Some(i) = cur.filter(|_| self.busy == 0) {
+            (i.opcode.effect)(&mut self.registers, &i.operands);
+            self.pc += 1;
+        }
     }
 
-    fn signal_strength(&self) -> i32 {
-        self.reg * self.clock
+    pub fn run(&mut self, observers: &mut [&mut dyn Observer]) {
+        while !self.is_finished() {
+            self.tick(observers);
+        }
+    }
+
+    pub fn run_for(&mut self, cycles: u64, observers: &mut [&mut dyn Observer]) {
+        (0..cycles).for_each(|_| self.tick(observers));
     }
 }
 
-pub fn part_1(input: &Input) -> i32 {
-    let mut machine = Machine::new(input.iter());
-    let key_cycles: [i32; 6] = [20, 60, 100, 140, 180, 220];
-
-    key_cycles
-        .iter()
-        .map(|&key_cycle| {
-            (machine.clock..key_cycle).for_each(|_| machine.tick());
-            machine.signal_strength()
-        })
-        .sum()
+// Sums the signal strength (the cycle number times `x`) during some cycles, wrapping like the
+// registers do.
+pub struct SignalStrength<'a> {
+    pub cycles: &'a [u64],
+    pub total: Word,
 }
 
-pub fn part_2(input: &Input) -> String {
-    const WIDTH: usize = 40;
-    const HEIGHT: usize = 6;
-    let mut machine = Machine::new(input.iter());
-    let mut screen = String::with_capacity(1 + (WIDTH + 1) * HEIGHT);
-    screen.push('\n');
-    for _ in 0..HEIGHT {
-        for col in 0i32..(WIDTH as i32) {
-            screen.push(if (machine.reg - col).abs() < 2 {
-                '#'
-            } else {
-                '.'
-            });
-            machine.tick();
+impl Observer for SignalStrength<'_> {
+    fn cycle(&mut self, cycle: u64, registers: &Registers) {
+        if self.cycles.contains(&cycle) {
+            let strength = (cycle as Word).wrapping_mul(registers.get('x'));
+            self.total = self.total.wrapping_add(strength);
+        }
+    }
+}
+
+// Draws a pixel each cycle, lit when the sprite (3 pixels wide and centred on `x`) covers it.
+pub struct Crt {
+    width: usize,
+    height: usize,
+    pixels: Vec<bool>,
+}
+
+impl Crt {
+    pub fn new(width: usize, height: usize) -> Self {
+        Crt {
+            width,
+            height,
+            pixels: Vec::with_capacity(width * height),
+        }
+    }
+
+    // The number of cycles it takes to draw the whole screen.
+    pub fn cycles(&self) -> u64 {
+        (self.width * self.height) as u64
+    }
+}
+
+impl Observer for Crt {
+    fn cycle(&mut self, _cycle: u64, registers: &Registers) {
+        if (self.pixels.len() as u64) < self.cycles() {
+            let col = (self.pixels.len() % self.width) as Word;
+            self.pixels.push(registers.get('x').abs_diff(col) < 2);
         }
-        screen.push('\n');
     }
-    screen
+}
+
+impl fmt::Display for Crt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.pixels.chunks(self.width) {
+            let line: String = row.iter().map(|&p| if p { '#' } else { '.' }).collect();
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+pub fn part_1(input: &Input) -> Word {
+    let mut signal = SignalStrength {
+        cycles: &[20, 60, 100, 140, 180, 220],
+        total: 0,
+    };
+    Machine::new(input).run_for(220, &mut [&mut signal]);
+    signal.total
+}
+
+pub fn part_2(input: &Input) -> String {
+    let mut crt = Crt::new(40, 6);
+    Machine::new(input).run_for(crt.cycles(), &mut [&mut crt]);
+    format!("\n{crt}")
 }
 
 #[cfg(test)]
@@ -247,7 +461,7 @@ mod tests {
             "
 
         });
-        // assert_eq!(part_1(&input), 13140);
+        assert_eq!(part_1(&input), 13140);
         assert_eq!(
             part_2(&input),
             indoc! {
@@ -263,4 +477,54 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_machine() {
+        let input = input_generator("set y 5000000000\nmuly 3\naddx y\nnoop\nsub y x");
+        let mut machine = Machine::new(&input);
+        let mut xs = vec![];
+        let mut record = |_, r: &Registers| xs.push(r.get('x'));
+        let mut signal = SignalStrength {
+            cycles: &[2, 8],
+            total: 0,
+        };
+        machine.run(&mut [&mut record, &mut signal]);
+        assert!(machine.is_finished());
+        assert_eq!(machine.registers.get('y'), -1);
+        assert_eq!(signal.total, 2 + 8 * 15_000_000_001);
+        assert_eq!(
+            xs,
+            [
+                1,
+                1,
+                1,
+                1,
+                1,
+                1,
+                1,
+                15_000_000_001,
+                15_000_000_001,
+                15_000_000_001
+            ]
+        );
+
+        // overflow wraps instead of panicking
+        let input =
+            input_generator("set y 9223372036854775807\nmuly 2\naddx y\nsubx 9223372036854775807");
+        let mut machine = Machine::new(&input);
+        machine.run(&mut []);
+        assert_eq!(machine.registers.get('y'), -2);
+        assert_eq!(machine.registers.get('x'), Word::MIN);
+        // every cycle that's sampled is even, so each strength wraps to 0
+        assert_eq!(part_1(&input), 0);
+        let mut crt = Crt::new(4, 1);
+        Machine::new(&input_generator("set x -9223372036854775808\nnoop\nsetx 2"))
+            .run_for(crt.cycles(), &mut [&mut crt]);
+        assert_eq!(crt.to_string(), "#..#\n");
+
+        // the machine idles once the program is done
+        let mut crt = Crt::new(6, 1);
+        Machine::new(&input_generator("addx 2")).run_for(crt.cycles(), &mut [&mut crt]);
+        assert_eq!(crt.to_string(), "#####.\n");
+    }
 }
@@ -16,6 +16,8 @@ use lazy_static::lazy_static;
 use regex::Regex;
 use rustc_hash::{FxHashMap, FxHashSet};
 
+use crate::runner::cancel;
+
 pub type ValveId = u8;
 
 pub struct Valve {
@@ -135,6 +137,7 @@ pub fn part_1(input: &Input) -> u32 {
     let mut most_pressure = 0;
     for round in 0..ROUNDS {
         for _ in 0..queue.len() {
+            cancel::checkpoint();
             let (pos, state) = queue.pop_front().unwrap();
             let ValveState {
                 released_pressure,
@@ -222,6 +225,7 @@ pub fn part_2(input: &Input) -> u32 {
     let mut most_pressure = 0;
     for round in 0..ROUNDS {
         for _ in 0..queue.len() {
+            cancel::checkpoint();
             let (me_pos, el_pos, state) = queue.pop_front().unwrap();
             let ValveState {
                 released_pressure,
@@ -13,6 +13,8 @@ use rayon::prelude::*;
 use strum::{EnumCount, IntoEnumIterator};
 use strum_macros::{EnumCount, EnumIter, EnumString};
 
+use crate::runner::cancel;
+
 #[derive(Debug, Copy, Clone, EnumString, EnumCount, EnumIter, PartialEq, Eq, PartialOrd, Ord)]
 #[strum(ascii_case_insensitive)]
 pub enum Resource {
@@ -250,6 +252,7 @@ impl State {
 
 impl Blueprint {
     fn branch_and_bound(&self, state: State, best: &mut u8) {
+        cancel::checkpoint();
         *best = state.secured_geodes.max(*best);
         for next in state.branch(self) {
             if next.bound(self) > *best {
@@ -268,18 +271,20 @@ impl Blueprint {
 }
 
 pub fn part_1(input: &Input) -> u32 {
+    let token = cancel::current();
     input
         .par_iter()
         .enumerate()
-        .map(|(i, b)| (i + 1) as u32 * b.solve(24) as u32)
+        .map(|(i, b)| (i + 1) as u32 * cancel::with_token(token.clone(), || b.solve(24)) as u32)
         .sum()
 }
 
 pub fn part_2(input: &Input) -> u32 {
+    let token = cancel::current();
     input
         .par_iter()
         .take(3)
-        .map(|b| b.solve(32) as u32)
+        .map(|b| cancel::with_token(token.clone(), || b.solve(32)) as u32)
         .product()
 }
 
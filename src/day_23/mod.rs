@@ -1,5 +1,7 @@
 use std::fmt::{Debug, Write};
 
+use crate::runner::cancel;
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Tile {
     Elf,
@@ -242,7 +244,12 @@ pub fn part_1(input: &Input) -> usize {
 
 pub fn part_2(input: &Input) -> usize {
     let mut input = input.clone();
-    (1..).find(|_| !input.execute_round()).unwrap()
+    (1..)
+        .find(|_| {
+            cancel::checkpoint();
+            !input.execute_round()
+        })
+        .unwrap()
 }
 
 #[cfg(test)]
@@ -0,0 +1,39 @@
+// The JSON written by the day 7 disk formats and the HTTP server, which is simple enough to write
+// by hand.
+
+// Appends the string quoted, escaping quotes, backslashes and control characters.
+pub fn write_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+pub fn string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    write_string(s, &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test() {
+        assert_eq!(string("a\"b\\\nc"), r#""a\"b\\\nc""#);
+        assert_eq!(string("café\t\u{1}"), r#""café\t\u0001""#);
+        let mut out = "[".to_string();
+        write_string("", &mut out);
+        assert_eq!(out, r#"["""#);
+    }
+}
@@ -1,5 +1,9 @@
 #![feature(portable_simd)]
 
+pub mod capi;
+pub mod json;
+pub mod runner;
+
 pub mod day_01;
 pub mod day_02;
 pub mod day_03;
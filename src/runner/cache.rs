@@ -0,0 +1,127 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use super::Day;
+
+pub const DEFAULT_DIR: &str = ".aoc_cache";
+
+// An on-disk store of answers, keyed by the day, part, input and the source of the day's
+// module (along with the crate version), so that editing a day invalidates its answers.
+pub struct Cache {
+    dir: PathBuf,
+}
+
+// 128-bit FNV-1a over the bytes of each part, each prefixed with its length so that they can't
+// run into each other. Unlike the `Hash` impls (and `FxHasher`, whose output depends on the word
+// size) this is fixed, so keys written by one build are still valid in the next. It isn't
+// cryptographic, so entries also record the length of their input, which is checked on read.
+fn hash(parts: &[&[u8]]) -> u128 {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+    let mut hash = OFFSET;
+    for part in parts {
+        for &b in (part.len() as u64).to_le_bytes().iter().chain(part.iter()) {
+            hash ^= b as u128;
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    hash
+}
+
+impl Cache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, day: &Day, part: u32, input: &str) -> PathBuf {
+        let source_hash = hash(&[env!("CARGO_PKG_VERSION").as_bytes(), day.source.as_bytes()]);
+        let input_hash = hash(&[input.as_bytes()]);
+        self.dir.join(format!(
+            "day{}-part{part}-{input_hash:032x}-{source_hash:032x}.txt",
+            day.day
+        ))
+    }
+
+    // An entry is the length of the input on the first line, followed by the answer.
+    pub fn get(&self, day: &Day, part: u32, input: &str) -> Option<String> {
+        let entry = fs::read_to_string(self.path(day, part, input)).ok()?;
+        let (len, answer) = entry.split_once('\n')?;
+        (len.parse() == Ok(input.len())).then(|| answer.to_string())
+    }
+
+    pub fn insert(&self, day: &Day, part: u32, input: &str, answer: &str) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(
+            self.path(day, part, input),
+            format!("{}\n{answer}", input.len()),
+        )
+    }
+
+    pub fn get_or_solve<F: FnOnce() -> String>(
+        &self,
+        day: &Day,
+        part: u32,
+        input: &str,
+        solve: F,
+    ) -> String {
+        self.get(day, part, input).unwrap_or_else(|| {
+            let answer = solve();
+            // failing to write to the cache shouldn't stop us from reporting the answer
+            let _ = self.insert(day, part, input, &answer);
+            answer
+        })
+    }
+
+    pub fn clear(&self) -> io::Result<()> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::get_day;
+
+    #[test]
+    fn test() {
+        let dir = std::env::temp_dir().join(format!("aoc_cache_test_{}", std::process::id()));
+        let cache = Cache::new(&dir);
+        let day_01 = get_day(1).unwrap();
+        let day_02 = get_day(2).unwrap();
+
+        assert_eq!(
+            cache.get_or_solve(day_01, 1, "1", || "24000".into()),
+            "24000"
+        );
+        assert_eq!(
+            cache.get_or_solve(day_01, 1, "1", || unreachable!()),
+            "24000"
+        );
+        assert_eq!(cache.get(day_01, 2, "1"), None);
+        assert_eq!(cache.get(day_01, 1, "2"), None);
+        assert_eq!(cache.get(day_02, 1, "1"), None);
+
+        // a colliding entry for an input of another length isn't used
+        fs::write(cache.path(day_01, 2, "1"), "2\nwrong").unwrap();
+        assert_eq!(cache.get(day_01, 2, "1"), None);
+        assert_eq!(
+            cache.get_or_solve(day_01, 2, "1", || "45000".into()),
+            "45000"
+        );
+        assert_eq!(cache.get(day_01, 2, "1"), Some("45000".to_string()));
+        cache.insert(day_01, 1, "3", "a\nb").unwrap();
+        assert_eq!(cache.get(day_01, 1, "3"), Some("a\nb".to_string()));
+
+        cache.clear().unwrap();
+        assert_eq!(cache.get(day_01, 1, "1"), None);
+        cache.clear().unwrap();
+
+        // the keys must not change between builds
+        assert_eq!(hash(&[]), 0x6c62272e07bb014262b821756295c58d);
+        assert_ne!(hash(&[b"ab", b"c"]), hash(&[b"a", b"bc"]));
+    }
+}
@@ -0,0 +1,54 @@
+use std::cell::RefCell;
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+// Solvers can't return early without changing their signatures, so cancellation is
+// cooperative: long-running loops call `checkpoint`, which unwinds with a `Cancelled`
+// payload once the token installed on the current thread has been cancelled.
+
+#[derive(Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+pub struct Cancelled;
+
+thread_local! {
+    static CURRENT: RefCell<Option<CancellationToken>> = const { RefCell::new(None) };
+}
+
+pub fn current() -> Option<CancellationToken> {
+    CURRENT.with(|c| c.borrow().clone())
+}
+
+struct Restore(Option<CancellationToken>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        CURRENT.with(|c| *c.borrow_mut() = self.0.take());
+    }
+}
+
+// Installs the token on the current thread for the duration of `f`. Solvers that use
+// other threads (e.g. with rayon) need to pass `current()` into them with this.
+pub fn with_token<T, F: FnOnce() -> T>(token: Option<CancellationToken>, f: F) -> T {
+    let _restore = Restore(CURRENT.with(|c| c.replace(token)));
+    f()
+}
+
+pub fn checkpoint() {
+    let cancelled = CURRENT.with(|c| c.borrow().as_ref().is_some_and(|t| t.is_cancelled()));
+    if cancelled {
+        // `resume_unwind` skips the panic hook, so this isn't reported as a panic
+        panic::resume_unwind(Box::new(Cancelled));
+    }
+}
@@ -0,0 +1,253 @@
+use std::any::Any;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::*;
+
+use self::cancel::{CancellationToken, Cancelled};
+
+pub mod cache;
+pub mod cancel;
+
+pub type Solver = fn(&str) -> String;
+
+pub struct Day {
+    pub day: u32,
+    pub source: &'static str,
+    pub part_1: Solver,
+    pub part_2: Solver,
+}
+
+impl Day {
+    pub fn part(&self, part: u32) -> Option<Solver> {
+        match part {
+            1 => Some(self.part_1),
+            2 => Some(self.part_2),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! day_with_generator {
+    ($m:ident, $d:expr) => {
+        Day {
+            day: $d,
+            source: include_str!(concat!("../", stringify!($m), "/mod.rs")),
+            part_1: |i| $m::part_1(&$m::input_generator(i)).to_string(),
+            part_2: |i| $m::part_2(&$m::input_generator(i)).to_string(),
+        }
+    };
+}
+
+macro_rules! day {
+    ($m:ident, $d:expr) => {
+        Day {
+            day: $d,
+            source: include_str!(concat!("../", stringify!($m), "/mod.rs")),
+            part_1: |i| $m::part_1(i).to_string(),
+            part_2: |i| $m::part_2(i).to_string(),
+        }
+    };
+}
+
+pub static DAYS: &[Day] = &[
+    day_with_generator!(day_01, 1),
+    day_with_generator!(day_02, 2),
+    day_with_generator!(day_03, 3),
+    day_with_generator!(day_04, 4),
+    day_with_generator!(day_05, 5),
+    day!(day_06, 6),
+    day_with_generator!(day_07, 7),
+    day_with_generator!(day_08, 8),
+    day!(day_09, 9),
+    day_with_generator!(day_10, 10),
+    day_with_generator!(day_11, 11),
+    day_with_generator!(day_12, 12),
+    day_with_generator!(day_13, 13),
+    day_with_generator!(day_14, 14),
+    day_with_generator!(day_15, 15),
+    day_with_generator!(day_16, 16),
+    day_with_generator!(day_17, 17),
+    day_with_generator!(day_18, 18),
+    day_with_generator!(day_19, 19),
+    day_with_generator!(day_20, 20),
+    day_with_generator!(day_21, 21),
+    day_with_generator!(day_22, 22),
+    day_with_generator!(day_23, 23),
+    day_with_generator!(day_24, 24),
+    day!(day_25, 25),
+];
+
+pub fn get_day(day: u32) -> Option<&'static Day> {
+    DAYS.iter().find(|d| d.day == day)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Failure {
+    Panic(String),
+    Timeout,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Panic(msg) => write!(f, "panicked: {msg}"),
+            Failure::Timeout => write!(f, "timed out"),
+        }
+    }
+}
+
+impl From<Box<dyn Any + Send>> for Failure {
+    fn from(payload: Box<dyn Any + Send>) -> Self {
+        if payload.is::<Cancelled>() {
+            return Failure::Timeout;
+        }
+        let msg = match payload.downcast::<String>() {
+            Ok(s) => *s,
+            Err(payload) => match payload.downcast::<&str>() {
+                Ok(s) => s.to_string(),
+                Err(_) => "unknown panic".to_string(),
+            },
+        };
+        Failure::Panic(msg)
+    }
+}
+
+pub struct Outcome {
+    pub answer: Result<String, Failure>,
+    pub elapsed: Duration,
+    // a timed-out solver was still running in the background, so `elapsed` is probably too long
+    pub unreliable: bool,
+}
+
+// The same as the default for the main thread on linux, which some of the recursive
+// solutions rely on.
+const STACK_SIZE: usize = 8 * 1024 * 1024;
+
+// The number of timed-out solvers still running in the background, see `with_timeout`.
+static ABANDONED: AtomicUsize = AtomicUsize::new(0);
+
+// Whether any timed-out solver is still running, which skews the timings of everything else.
+pub fn timings_unreliable() -> bool {
+    ABANDONED.load(Ordering::SeqCst) > 0
+}
+
+const RUNNING: u8 = 0;
+const FINISHED: u8 = 1;
+const TIMED_OUT: u8 = 2;
+
+// Runs `f` on its own thread, turning any panic (i.e. an `expect` or `panic!` in the solution)
+// into a failure containing the panic message.
+// If the timeout expires the thread's cancellation token is cancelled and we return without
+// waiting for it. Solutions that never reach a `cancel::checkpoint` will keep running in
+// the background until they finish, and `timings_unreliable` is true until they do.
+pub fn with_timeout<T, F>(timeout: Option<Duration>, f: F) -> Result<T, Failure>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let token = CancellationToken::default();
+    let thread_token = token.clone();
+    let state = Arc::new(AtomicU8::new(RUNNING));
+    let thread_state = state.clone();
+    let (tx, rx) = mpsc::channel();
+    thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || {
+            let result = cancel::with_token(Some(thread_token), || {
+                panic::catch_unwind(AssertUnwindSafe(f))
+            });
+            if thread_state.swap(FINISHED, Ordering::SeqCst) == TIMED_OUT {
+                ABANDONED.fetch_sub(1, Ordering::SeqCst);
+            }
+            let _ = tx.send(result.map_err(Failure::from));
+        })
+        .expect("couldn't spawn solver thread");
+
+    let result = match timeout {
+        Some(timeout) => rx.recv_timeout(timeout),
+        None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+    };
+    match result {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => {
+            token.cancel();
+            // counted first so that the thread finishing in between can't take it below zero
+            ABANDONED.fetch_add(1, Ordering::SeqCst);
+            if state
+                .compare_exchange(RUNNING, TIMED_OUT, Ordering::SeqCst, Ordering::SeqCst)
+                .is_err()
+            {
+                ABANDONED.fetch_sub(1, Ordering::SeqCst);
+            }
+            Err(Failure::Timeout)
+        }
+        Err(RecvTimeoutError::Disconnected) => {
+            unreachable!("solver thread exited without a result")
+        }
+    }
+}
+
+pub fn run(solver: Solver, input: &str, timeout: Option<Duration>) -> Outcome {
+    let unreliable = timings_unreliable();
+    let instant = Instant::now();
+    let input = input.to_string();
+    let answer = with_timeout(timeout, move || solver(&input));
+    Outcome {
+        answer,
+        elapsed: instant.elapsed(),
+        unreliable,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test() {
+        assert_eq!(DAYS.len(), 25);
+        assert!(DAYS.iter().enumerate().all(|(i, d)| d.day == i as u32 + 1));
+
+        let day_01 = get_day(1).unwrap();
+        let outcome = run(day_01.part_1, "1000\n2000\n\n4000", None);
+        assert_eq!(outcome.answer, Ok("4000".to_string()));
+        let outcome = run(day_01.part(2).unwrap(), "1000\nnot a number", None);
+        assert!(matches!(
+            outcome.answer,
+            Err(Failure::Panic(msg)) if msg.contains("Could not parse calories as int")
+        ));
+        assert!(get_day(26).is_none());
+
+        let (tx, rx) = mpsc::channel();
+        let result = with_timeout(Some(Duration::from_millis(10)), move || loop {
+            if tx.send(()).is_err() {
+                break;
+            }
+            cancel::checkpoint();
+            thread::sleep(Duration::from_millis(1));
+        });
+        assert_eq!(result, Err(Failure::Timeout));
+        // the solver should notice the cancellation and drop its end of the channel
+        while rx.recv().is_ok() {}
+
+        // a solver without checkpoints keeps running, which is flagged until it finishes
+        let (tx, rx) = mpsc::channel::<()>();
+        let result = with_timeout(Some(Duration::from_millis(10)), move || {
+            let _ = rx.recv();
+        });
+        assert_eq!(result, Err(Failure::Timeout));
+        assert!(timings_unreliable());
+        assert!(run(day_01.part_1, "1", None).unreliable);
+        drop(tx);
+        while timings_unreliable() {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(!run(day_01.part_1, "1", None).unreliable);
+    }
+}
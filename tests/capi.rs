@@ -0,0 +1,94 @@
+use std::fs;
+
+use advent_of_code_2022::capi;
+
+// Declared the way a C caller sees it in the header, so these tests go through the C ABI.
+extern "C" {
+    fn aoc_solve(
+        year: u32,
+        day: u32,
+        part: u32,
+        input_ptr: *const u8,
+        len: usize,
+        out_buf: *mut u8,
+        out_len: *mut usize,
+    ) -> i32;
+}
+
+const HEADER_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/include/aoc_2022.h");
+
+fn solve(year: u32, day: u32, part: u32, input: &[u8], capacity: usize) -> (i32, Vec<u8>) {
+    let mut out = vec![0; capacity];
+    let mut out_len = capacity;
+    let status = unsafe {
+        aoc_solve(
+            year,
+            day,
+            part,
+            input.as_ptr(),
+            input.len(),
+            out.as_mut_ptr(),
+            &mut out_len,
+        )
+    };
+    out.truncate(out_len.min(capacity));
+    (status, out)
+}
+
+#[test]
+fn test_solve() {
+    let input = b"1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000";
+    assert_eq!(solve(2022, 1, 1, input, 64), (0, b"24000".to_vec()));
+    assert_eq!(solve(2022, 1, 2, input, 64), (0, b"45000".to_vec()));
+    assert_eq!(
+        solve(2022, 6, 1, b"bvwbjplbgvbhsrlpgdmjqwftvncz", 64),
+        (0, b"5".to_vec())
+    );
+
+    assert_eq!(solve(2021, 1, 1, input, 64).0, 1);
+    assert_eq!(solve(2022, 26, 1, input, 64).0, 1);
+    assert_eq!(solve(2022, 1, 3, input, 64).0, 1);
+    assert_eq!(solve(2022, 1, 1, &[0xff], 64).0, 2);
+    assert_eq!(solve(2022, 1, 1, b"not calories", 64).0, 3);
+
+    let mut out_len = 2;
+    let status = unsafe {
+        aoc_solve(
+            2022,
+            1,
+            1,
+            input.as_ptr(),
+            input.len(),
+            [0u8; 2].as_mut_ptr(),
+            &mut out_len,
+        )
+    };
+    assert_eq!((status, out_len), (4, 5));
+
+    let status = unsafe {
+        aoc_solve(
+            2022,
+            1,
+            1,
+            std::ptr::null(),
+            0,
+            [0u8; 2].as_mut_ptr(),
+            &mut out_len,
+        )
+    };
+    assert_eq!(status, 5);
+}
+
+// Regenerate the header with `AOC_UPDATE_HEADER=1 cargo test --test capi`.
+#[test]
+fn test_header_up_to_date() {
+    let header = capi::header();
+    if std::env::var_os("AOC_UPDATE_HEADER").is_some() {
+        fs::write(HEADER_PATH, &header).unwrap();
+    }
+    assert_eq!(
+        fs::read_to_string(HEADER_PATH).unwrap(),
+        header,
+        "the header is out of date, regenerate it with `AOC_UPDATE_HEADER=1 cargo test --test capi`"
+    );
+}